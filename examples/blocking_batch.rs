@@ -0,0 +1,73 @@
+//! Example demonstrating batch processing without any async runtime.
+//!
+//! This example uses the blocking HTTP client together with the thread-based
+//! BatchSpanProcessor of the SDK, which exports from its own background thread.
+//! Unlike `sync_batch`, no Tokio runtime is created, which suits CLI tools and
+//! language extension modules that cannot host one.
+//!
+//! Run with:
+//! ```bash
+//! export LANGFUSE_PUBLIC_KEY="pk-lf-..."
+//! export LANGFUSE_SECRET_KEY="sk-lf-..."
+//! export LANGFUSE_HOST="https://cloud.langfuse.com"
+//!
+//! cargo run --example blocking_batch --features blocking-client
+//! ```
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use opentelemetry::global;
+    use opentelemetry::trace::{Span, SpanKind, Tracer};
+    use opentelemetry::KeyValue;
+    use opentelemetry_langfuse::ExporterBuilder;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use std::thread;
+    use std::time::Duration;
+
+    println!("Creating Langfuse exporter with a blocking HTTP client");
+
+    // Create the Langfuse exporter from environment variables
+    let exporter = ExporterBuilder::from_env()?
+        .with_blocking_http_client(reqwest::blocking::Client::new())
+        .build()?;
+
+    // The thread-based BatchSpanProcessor needs no async runtime
+    let provider = SdkTracerProvider::builder()
+        .with_resource(
+            Resource::builder()
+                .with_attributes([KeyValue::new("service.name", "blocking-batch-example")])
+                .build(),
+        )
+        .with_batch_exporter(exporter)
+        .build();
+
+    // Set as global provider
+    global::set_tracer_provider(provider.clone());
+
+    let tracer = global::tracer("blocking-batch-example");
+
+    println!("Creating spans (they will be batched)...");
+
+    for request_id in 1..=5 {
+        let mut span = tracer
+            .span_builder("handle-command")
+            .with_kind(SpanKind::Internal)
+            .with_attributes([KeyValue::new("request.id", request_id)])
+            .start(&tracer);
+
+        // Simulate some work
+        thread::sleep(Duration::from_millis(10));
+
+        span.end();
+        println!("  - Command {} processed", request_id);
+    }
+
+    println!("\nShutting down (this will flush any remaining spans)...");
+
+    // Shutdown the provider - this flushes remaining spans
+    provider.shutdown()?;
+
+    println!("All spans exported. Check your Langfuse dashboard for the traces.");
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 //! Authentication utilities for Langfuse.
 
 use crate::constants::{ENV_LANGFUSE_PUBLIC_KEY, ENV_LANGFUSE_SECRET_KEY};
+use crate::secret::SecretString;
 use base64::{engine::general_purpose::STANDARD, Engine};
 use std::env;
 
@@ -68,6 +69,77 @@ pub fn build_auth_header_from_env() -> Result<String, crate::Error> {
     Ok(build_auth_header(&public_key, &secret_key))
 }
 
+/// Splits the value of a Basic authentication header into the public and the
+/// secret key.
+///
+/// Returns `None` for other authentication schemes or malformed values.
+pub(crate) fn decode_basic_auth(auth_header: &str) -> Option<(String, SecretString)> {
+    let encoded = auth_header.strip_prefix("Basic ")?;
+    let decoded = SecretString::new(String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?);
+    let (public_key, secret_key) = decoded.expose_secret().split_once(':')?;
+    Some((public_key.to_string(), SecretString::new(secret_key)))
+}
+
+/// Checks the format of a Basic Authorization header holding Langfuse keys.
+///
+/// Other authentication schemes, e.g. of a proxy, are not checked.
+pub(crate) fn check_auth_header(auth_header: &str) -> Result<(), crate::Error> {
+    let Some(encoded) = auth_header.strip_prefix("Basic ") else {
+        return Ok(());
+    };
+    let invalid =
+        |reason: &str, source: crate::error::BoxError| crate::Error::InvalidCredentialFormat {
+            reason: reason.to_string(),
+            source: Some(source),
+        };
+    let decoded = STANDARD
+        .decode(encoded.trim())
+        .map_err(|err| invalid("the Basic Authorization header is not base64", err.into()))?;
+    let decoded = SecretString::new(
+        String::from_utf8(decoded)
+            .map_err(|err| invalid("the Basic Authorization header is not UTF-8", err.into()))?,
+    );
+    match decoded.expose_secret().split_once(':') {
+        Some((public_key, secret_key)) => check_key_format(public_key, secret_key),
+        None => Err(crate::Error::InvalidCredentialFormat {
+            reason: "the Basic Authorization header holds no public:secret key pair".into(),
+            source: None,
+        }),
+    }
+}
+
+/// Rejects empty, padded or swapped Langfuse keys.
+fn check_key_format(public_key: &str, secret_key: &str) -> Result<(), crate::Error> {
+    let reason = if public_key.is_empty() || secret_key.is_empty() {
+        "the public or the secret key is empty"
+    } else if public_key
+        .chars()
+        .chain(secret_key.chars())
+        .any(char::is_whitespace)
+    {
+        "the keys contain whitespace"
+    } else if public_key.starts_with("sk-lf-") || secret_key.starts_with("pk-lf-") {
+        "the public and secret keys are swapped"
+    } else {
+        return Ok(());
+    };
+    Err(crate::Error::InvalidCredentialFormat {
+        reason: reason.to_string(),
+        source: None,
+    })
+}
+
+/// Masks a public key for display, keeping its prefix and last characters.
+pub(crate) fn mask_public_key(public_key: &str) -> String {
+    let chars: Vec<char> = public_key.chars().collect();
+    if chars.len() < 16 {
+        return "***".to_string();
+    }
+    let head: String = chars[..10].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,6 +152,27 @@ mod tests {
         assert_eq!(auth, expected);
     }
 
+    #[test]
+    fn test_check_auth_header() {
+        assert!(check_auth_header(&build_auth_header("pk-lf-1", "sk-lf-1")).is_ok());
+        assert!(check_auth_header("Bearer proxy-token").is_ok());
+
+        for header in [
+            build_auth_header("sk-lf-1", "pk-lf-1"),
+            build_auth_header("pk-lf-1", "sk-lf-1\n"),
+            build_auth_header("", "sk-lf-1"),
+            "Basic not-base64!".to_string(),
+        ] {
+            assert!(matches!(
+                check_auth_header(&header),
+                Err(crate::Error::InvalidCredentialFormat { .. })
+            ));
+        }
+
+        let err = check_auth_header("Basic not-base64!").unwrap_err();
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
     #[test]
     #[serial]
     fn test_build_auth_header_from_env() {
@@ -0,0 +1,352 @@
+//! Splitting of span batches to stay below the Langfuse request size limit.
+//!
+//! Langfuse rejects request bodies above a size limit with `413 Payload Too
+//! Large`, which would drop every span of the batch. Batches are therefore
+//! split into requests whose encoded size stays below a configurable limit,
+//! and single spans that exceed the limit on their own have their largest
+//! string attributes truncated.
+//!
+//! Sizes are measured in the [`Encoding`] of the request body, since JSON
+//! bodies are several times larger than protobuf ones.
+
+use opentelemetry::{StringValue, Value};
+use opentelemetry_proto::tonic::resource::v1::Resource as ProtoResource;
+use opentelemetry_proto::tonic::trace::v1::ResourceSpans;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use prost::Message;
+use std::mem;
+
+/// Default maximum encoded size of a single export request (4 MiB).
+pub(crate) const DEFAULT_MAX_BATCH_SIZE: usize = 4 * 1024 * 1024;
+
+/// Marker appended to truncated attribute values.
+pub(crate) const TRUNCATION_MARKER: &str = "...[truncated]";
+
+/// Attribute values are never truncated below this length, in bytes.
+const MIN_TRUNCATED_LEN: usize = 256;
+
+/// Encoding of the spans in the body of an export request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    /// OTLP protobuf (`Protocol::HttpBinary`).
+    Protobuf,
+    /// OTLP JSON (`Protocol::HttpJson`).
+    Json,
+    /// Events of the ingestion API (`Transport::Ingestion`).
+    Ingestion,
+}
+
+/// Returns the encoded size of a `len` bytes message in a repeated field.
+fn field_size(len: usize) -> usize {
+    1 + prost::length_delimiter_len(len) + len
+}
+
+/// Returns the encoded size of the resource shared by all spans of a request.
+///
+/// Ingestion requests repeat the resource in every observation, so for them
+/// this is the size added to each span.
+pub(crate) fn resource_size(resource: &Resource, encoding: Encoding) -> usize {
+    if encoding == Encoding::Ingestion {
+        return crate::ingestion::resource_size(resource);
+    }
+
+    let resource = ResourceAttributesWithSchema::from(resource);
+    let envelope = ResourceSpans {
+        resource: Some(ProtoResource {
+            attributes: resource.attributes.0,
+            dropped_attributes_count: 0,
+            entity_refs: Vec::new(),
+        }),
+        scope_spans: Vec::new(),
+        schema_url: resource.schema_url.unwrap_or_default(),
+    };
+    match encoding {
+        Encoding::Json => json_size(&envelope),
+        _ => field_size(envelope.encoded_len()),
+    }
+}
+
+/// Returns the encoded size of a span, including its instrumentation scope.
+///
+/// Spans sharing a scope (or a trace, for the ingestion API) are grouped in
+/// the actual request, so the sum of span sizes slightly overestimates the
+/// size of the request.
+pub(crate) fn span_size(span: &SpanData, encoding: Encoding) -> usize {
+    if encoding == Encoding::Ingestion {
+        return crate::ingestion::span_size(span);
+    }
+
+    let spans = ResourceSpans::new(span.clone(), &ResourceAttributesWithSchema::default());
+    match encoding {
+        Encoding::Json => json_size(&spans),
+        _ => field_size(spans.encoded_len()),
+    }
+}
+
+/// Returns the size of a message in a JSON array, including its separator.
+#[cfg(feature = "http-json")]
+fn json_size(message: &ResourceSpans) -> usize {
+    serde_json::to_vec(message).map_or_else(|_| message.encoded_len(), |json| json.len()) + 1
+}
+
+/// Without the `http-json` feature, requests cannot use JSON.
+#[cfg(not(feature = "http-json"))]
+fn json_size(message: &ResourceSpans) -> usize {
+    field_size(message.encoded_len())
+}
+
+/// Splits a batch into chunks whose encoded size stays below `max_size`.
+///
+/// Spans that exceed the limit on their own are shrunk with
+/// [`shrink_span`] and sent in a chunk of their own. An empty batch yields a
+/// single empty chunk.
+pub(crate) fn split_batch(
+    batch: Vec<SpanData>,
+    resource_size: usize,
+    max_size: usize,
+    encoding: Encoding,
+) -> Vec<Vec<SpanData>> {
+    let (budget, span_overhead) = match encoding {
+        Encoding::Ingestion => (
+            max_size.saturating_sub(crate::ingestion::ENVELOPE_SIZE),
+            resource_size,
+        ),
+        _ => (max_size.saturating_sub(resource_size), 0),
+    };
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_size = 0;
+
+    for mut span in batch {
+        let span_budget = budget.saturating_sub(span_overhead);
+        let mut size = span_size(&span, encoding);
+        if size > span_budget {
+            size = shrink_span(&mut span, span_budget, encoding);
+        }
+        size += span_overhead;
+        if !chunk.is_empty() && chunk_size + size > budget {
+            chunks.push(mem::take(&mut chunk));
+            chunk_size = 0;
+        }
+        chunk_size += size;
+        chunk.push(span);
+    }
+
+    if !chunk.is_empty() || chunks.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Truncates the largest string attributes of a span until it fits `budget`.
+///
+/// Span and event attributes are considered. Truncated values end with
+/// [`TRUNCATION_MARKER`]. Returns the resulting encoded size, which may still
+/// exceed `budget` if no attribute is left to truncate.
+pub(crate) fn shrink_span(span: &mut SpanData, budget: usize, encoding: Encoding) -> usize {
+    let mut size = span_size(span, encoding);
+    while size > budget {
+        let Some(value) = largest_string_attribute(span) else {
+            break;
+        };
+
+        let len = value.as_str().len();
+        if len <= MIN_TRUNCATED_LEN + TRUNCATION_MARKER.len() {
+            break;
+        }
+
+        let excess = size - budget;
+        let keep = len
+            .saturating_sub(excess + TRUNCATION_MARKER.len())
+            .max(MIN_TRUNCATED_LEN);
+        *value = Value::String(truncate(value.as_str().as_ref(), keep));
+        size = span_size(span, encoding);
+    }
+    size
+}
+
+/// Returns the longest string attribute value of a span or its events.
+fn largest_string_attribute(span: &mut SpanData) -> Option<&mut Value> {
+    let events = span
+        .events
+        .events
+        .iter_mut()
+        .flat_map(|event| event.attributes.iter_mut());
+
+    span.attributes
+        .iter_mut()
+        .chain(events)
+        .map(|kv| &mut kv.value)
+        .filter(|value| matches!(value, Value::String(_)))
+        .max_by_key(|value| value.as_str().len())
+}
+
+/// Truncates `value` to at most `keep` bytes on a character boundary and
+/// appends the truncation marker.
+fn truncate(value: &str, keep: usize) -> StringValue {
+    let mut end = keep.min(value.len());
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &value[..end], TRUNCATION_MARKER).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::span_data;
+    use opentelemetry::KeyValue;
+    use std::time::SystemTime;
+
+    fn large_span(id: u64, len: usize) -> SpanData {
+        span_data(1, id, 0, vec![KeyValue::new("input", "x".repeat(len))])
+    }
+
+    #[test]
+    fn test_small_batch_is_not_split() {
+        let batch = vec![large_span(1, 100), large_span(2, 100)];
+
+        let chunks = split_batch(batch, 0, DEFAULT_MAX_BATCH_SIZE, Encoding::Protobuf);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 2);
+    }
+
+    #[test]
+    fn test_empty_batch_yields_one_empty_chunk() {
+        let chunks = split_batch(Vec::new(), 0, 1024, Encoding::Protobuf);
+
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_empty());
+    }
+
+    #[test]
+    fn test_batch_is_split_below_limit() {
+        let batch: Vec<_> = (1..=10).map(|id| large_span(id, 1000)).collect();
+        let limit = 3500;
+
+        let chunks = split_batch(batch, 0, limit, Encoding::Protobuf);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.iter().map(Vec::len).sum::<usize>(), 10);
+        for chunk in &chunks {
+            let size: usize = chunk
+                .iter()
+                .map(|span| span_size(span, Encoding::Protobuf))
+                .sum();
+            assert!(size <= limit);
+        }
+        // Span order is preserved
+        let ids: Vec<_> = chunks
+            .iter()
+            .flatten()
+            .map(|span| span.name.to_string())
+            .collect();
+        assert_eq!(ids.first().unwrap(), "span-1");
+        assert_eq!(ids.last().unwrap(), "span-10");
+    }
+
+    #[test]
+    fn test_resource_size_counts_against_limit() {
+        let resource = Resource::builder_empty()
+            .with_attribute(KeyValue::new("service.name", "x".repeat(2000)))
+            .build();
+        let batch = vec![large_span(1, 1000), large_span(2, 1000)];
+
+        let without = split_batch(batch.clone(), 0, 4000, Encoding::Protobuf);
+        let with = split_batch(
+            batch,
+            resource_size(&resource, Encoding::Protobuf),
+            4000,
+            Encoding::Protobuf,
+        );
+
+        assert_eq!(without.len(), 1);
+        assert_eq!(with.len(), 2);
+    }
+
+    #[test]
+    fn test_oversize_span_has_largest_attribute_truncated() {
+        let mut span = span_data(
+            1,
+            1,
+            0,
+            vec![
+                KeyValue::new("input", "i".repeat(10_000)),
+                KeyValue::new("output", "o".repeat(2_000)),
+                KeyValue::new("model", "gpt-4"),
+            ],
+        );
+
+        let size = shrink_span(&mut span, 5000, Encoding::Protobuf);
+
+        assert!(size <= 5000);
+        assert_eq!(size, span_size(&span, Encoding::Protobuf));
+        let input = span.attributes[0].value.as_str();
+        assert!(input.len() < 10_000);
+        assert!(input.ends_with(TRUNCATION_MARKER));
+        assert_eq!(span.attributes[1].value.as_str().len(), 2_000);
+        assert_eq!(span.attributes[2].value.as_str(), "gpt-4");
+    }
+
+    #[test]
+    fn test_shrink_gives_up_without_truncatable_attributes() {
+        let mut span = span_data(1, 1, 0, vec![KeyValue::new("count", 42)]);
+
+        let size = shrink_span(&mut span, 10, Encoding::Protobuf);
+
+        assert!(size > 10);
+        assert_eq!(span.attributes[0].value, Value::I64(42));
+    }
+
+    #[test]
+    fn test_sizes_follow_the_encoding() {
+        let span = span_data(
+            1,
+            1,
+            0,
+            vec![
+                KeyValue::new("langfuse.observation.input", "x".repeat(100)),
+                KeyValue::new("tokens", 42),
+            ],
+        );
+        let protobuf = span_size(&span, Encoding::Protobuf);
+
+        // Hex IDs, field names and RFC 3339 timestamps make JSON bodies larger
+        assert!(span_size(&span, Encoding::Ingestion) > protobuf);
+        #[cfg(feature = "http-json")]
+        assert!(span_size(&span, Encoding::Json) > protobuf);
+
+        // The estimate covers the actual request, which repeats the resource
+        let resource = Resource::builder_empty()
+            .with_attribute(KeyValue::new("service.name", "checkout"))
+            .build();
+        let mut exporter = crate::ingestion::IngestionExporter::new(
+            crate::transport::LangfuseHttpClient::new(reqwest::Client::new()),
+            String::new(),
+            Default::default(),
+        );
+        exporter.set_resource(&resource);
+        let spans = [span.clone(), span_data(1, 2, 1, vec![])];
+        let events = exporter.events(&spans, SystemTime::now());
+        let body = serde_json::to_vec(&serde_json::json!({ "batch": events })).unwrap();
+        let estimate = crate::ingestion::ENVELOPE_SIZE
+            + spans
+                .iter()
+                .map(|span| {
+                    resource_size(&resource, Encoding::Ingestion)
+                        + span_size(span, Encoding::Ingestion)
+                })
+                .sum::<usize>();
+        assert!(estimate >= body.len());
+    }
+
+    #[test]
+    fn test_truncate_respects_char_boundaries() {
+        let truncated = truncate("ééé", 3);
+
+        assert_eq!(truncated.as_str(), format!("é{}", TRUNCATION_MARKER));
+    }
+}
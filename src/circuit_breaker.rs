@@ -0,0 +1,375 @@
+//! Circuit breaker for exports that can never succeed.
+//!
+//! When the Langfuse keys have been rotated or the host is wrong, every export
+//! fails with `401`, `403` or `404` until the configuration is fixed. A
+//! [`CircuitBreaker`] opens after a number of consecutive failures of this kind
+//! and stops sending requests, except for a single probe request on a slow
+//! schedule. The first probe that is not rejected closes the circuit again.
+//!
+//! The breaker is a cheap handle: clones share the same state, so a clone kept
+//! by the application can report the export state, e.g. on an admin page.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{CircuitBreaker, CircuitState, ExporterBuilder};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let breaker = CircuitBreaker::new();
+//! let exporter = ExporterBuilder::from_env()?
+//!     .with_circuit_breaker(breaker.clone())
+//!     .build()?;
+//!
+//! // Later, e.g. on an admin page
+//! if let CircuitState::Open { reason, .. } = breaker.state() {
+//!     println!("Langfuse export disabled: {}", reason);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use http::StatusCode;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Why a circuit breaker opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitOpenReason {
+    /// Langfuse rejected the credentials (`401 Unauthorized` or `403 Forbidden`).
+    InvalidCredentials,
+    /// The endpoint does not exist (`404 Not Found`), e.g. because of a wrong host.
+    EndpointNotFound,
+}
+
+impl CircuitOpenReason {
+    /// Returns the reason for a response status, or `None` if the status does
+    /// not count as a failure.
+    fn from_status(status: StatusCode) -> Option<Self> {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Some(Self::InvalidCredentials),
+            StatusCode::NOT_FOUND => Some(Self::EndpointNotFound),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CircuitOpenReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCredentials => f.write_str("invalid credentials"),
+            Self::EndpointNotFound => f.write_str("endpoint not found"),
+        }
+    }
+}
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent normally.
+    Closed,
+    /// Requests are rejected without being sent, apart from periodic probes.
+    Open {
+        /// Why the circuit opened.
+        reason: CircuitOpenReason,
+        /// When the circuit opened.
+        since: SystemTime,
+    },
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => f.write_str("closed"),
+            Self::Open { reason, .. } => write!(f, "open: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Open {
+    reason: CircuitOpenReason,
+    since: SystemTime,
+    next_probe: Instant,
+    probing: bool,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    consecutive_failures: u32,
+    open: Option<Open>,
+}
+
+/// Circuit breaker that stops exports after repeated authentication or
+/// endpoint failures.
+///
+/// Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    probe_interval: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a circuit breaker with the default settings.
+    ///
+    /// The defaults open the circuit after 5 consecutive failures and probe
+    /// once per minute while it is open.
+    pub fn new() -> Self {
+        Self {
+            failure_threshold: 5,
+            probe_interval: Duration::from_secs(60),
+            inner: Arc::default(),
+        }
+    }
+
+    /// Sets the number of consecutive `401`, `403` or `404` responses after
+    /// which the circuit opens.
+    ///
+    /// Values below `1` are treated as `1`.
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Sets the delay between two probe requests while the circuit is open.
+    pub fn with_probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+
+    /// Returns the current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        match &self.lock().open {
+            Some(open) => CircuitState::Open {
+                reason: open.reason,
+                since: open.since,
+            },
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Returns whether the circuit is open.
+    pub fn is_open(&self) -> bool {
+        self.lock().open.is_some()
+    }
+
+    /// Closes the circuit, e.g. after the configuration has been fixed.
+    pub fn reset(&self) {
+        *self.lock() = Inner::default();
+    }
+
+    /// Checks whether a request may be sent.
+    ///
+    /// While the circuit is open, a single request is let through as a probe
+    /// once the probe interval has elapsed. All other requests are rejected
+    /// with the reason the circuit opened.
+    ///
+    /// The outcome of the request must be reported through the returned
+    /// [`Permit`]. A permit dropped without an outcome, e.g. because the export
+    /// was cancelled, counts as a request without response.
+    pub(crate) fn try_acquire(&self) -> Result<Permit<'_>, CircuitOpenReason> {
+        let mut inner = self.lock();
+        match &mut inner.open {
+            None => {}
+            Some(open) if !open.probing && Instant::now() >= open.next_probe => {
+                open.probing = true;
+            }
+            Some(open) => return Err(open.reason),
+        }
+        Ok(Permit {
+            breaker: Some(self),
+        })
+    }
+
+    /// Records the outcome of a request let through by [`try_acquire`](Self::try_acquire).
+    ///
+    /// `status` is `None` if no response was received.
+    fn record(&self, status: Option<StatusCode>) {
+        let mut inner = self.lock();
+        let next_probe = Instant::now() + self.probe_interval;
+
+        match status.map(CircuitOpenReason::from_status) {
+            Some(Some(reason)) => {
+                inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+                let threshold_reached = inner.consecutive_failures >= self.failure_threshold;
+                match &mut inner.open {
+                    Some(open) => {
+                        open.reason = reason;
+                        open.next_probe = next_probe;
+                        open.probing = false;
+                    }
+                    None if threshold_reached => {
+                        inner.open = Some(Open {
+                            reason,
+                            since: SystemTime::now(),
+                            next_probe,
+                            probing: false,
+                        });
+                    }
+                    None => {}
+                }
+            }
+            // Any other response means Langfuse accepts the endpoint and keys
+            Some(None) => *inner = Inner::default(),
+            // Without a response nothing is known, so only schedule the next probe
+            None => {
+                if let Some(open) = &mut inner.open {
+                    open.next_probe = next_probe;
+                    open.probing = false;
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Permission to send a request, returned by [`CircuitBreaker::try_acquire`].
+#[derive(Debug)]
+pub(crate) struct Permit<'a> {
+    breaker: Option<&'a CircuitBreaker>,
+}
+
+impl Permit<'_> {
+    /// Records the outcome of the request.
+    ///
+    /// `status` is `None` if no response was received.
+    pub(crate) fn record(mut self, status: Option<StatusCode>) {
+        if let Some(breaker) = self.breaker.take() {
+            breaker.record(status);
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        // The request was abandoned, so release the probe
+        if let Some(breaker) = self.breaker.take() {
+            breaker.record(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail(breaker: &CircuitBreaker, status: u16, times: u32) {
+        for _ in 0..times {
+            breaker
+                .try_acquire()
+                .unwrap()
+                .record(Some(StatusCode::from_u16(status).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new().with_failure_threshold(3);
+
+        fail(&breaker, 401, 2);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        fail(&breaker, 403, 1);
+        assert!(matches!(
+            breaker.state(),
+            CircuitState::Open {
+                reason: CircuitOpenReason::InvalidCredentials,
+                ..
+            }
+        ));
+        assert_eq!(
+            breaker.try_acquire().err(),
+            Some(CircuitOpenReason::InvalidCredentials)
+        );
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new().with_failure_threshold(2);
+
+        fail(&breaker, 404, 1);
+        fail(&breaker, 200, 1);
+        fail(&breaker, 404, 1);
+        assert!(!breaker.is_open());
+
+        fail(&breaker, 404, 1);
+        assert_eq!(
+            breaker.state().to_string(),
+            "open: endpoint not found".to_string()
+        );
+    }
+
+    #[test]
+    fn test_transient_failures_do_not_count() {
+        let breaker = CircuitBreaker::new().with_failure_threshold(1);
+
+        fail(&breaker, 503, 3);
+        breaker.record(None);
+
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_probe_closes_circuit() {
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(1)
+            .with_probe_interval(Duration::ZERO);
+        fail(&breaker, 401, 1);
+
+        // Only one probe at a time
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_err());
+
+        probe.record(Some(StatusCode::OK));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_abandoned_probe_releases_the_circuit() {
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(1)
+            .with_probe_interval(Duration::ZERO);
+        fail(&breaker, 401, 1);
+
+        // E.g. the export future was cancelled before a response arrived
+        drop(breaker.try_acquire().unwrap());
+
+        assert!(breaker.is_open());
+        breaker.try_acquire().unwrap().record(Some(StatusCode::OK));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_failed_probe_waits_for_next_interval() {
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(1)
+            .with_probe_interval(Duration::from_secs(60));
+        fail(&breaker, 401, 1);
+
+        assert!(breaker.try_acquire().is_err());
+        assert!(breaker.is_open());
+    }
+
+    #[test]
+    fn test_clones_share_state() {
+        let breaker = CircuitBreaker::new().with_failure_threshold(1);
+        let handle = breaker.clone();
+
+        fail(&breaker, 401, 1);
+        assert!(handle.is_open());
+
+        handle.reset();
+        assert!(!breaker.is_open());
+    }
+}
@@ -0,0 +1,664 @@
+//! File-based configuration of the exporter.
+//!
+//! [`LangfuseConfig`] mirrors the settings read by
+//! [`ExporterBuilder::from_env`](crate::ExporterBuilder::from_env) and
+//! implements `Deserialize`, so it can be loaded from a TOML, YAML or JSON
+//! file with the matching serde crate, or with [`LangfuseConfig::from_file`].
+//! Unset fields have the same defaults as unset environment variables.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{ExporterBuilder, LangfuseConfig};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // langfuse.toml:
+//! //
+//! // host = "https://cloud.langfuse.com"
+//! // public_key = "pk-lf-..."
+//! // secret_key_file = "/run/secrets/langfuse-secret-key"
+//! // timeout_ms = 10000
+//! //
+//! // [batch]
+//! // max_export_batch_size = 256
+//! // Requires the `toml` cargo feature
+//! let config = LangfuseConfig::from_file("langfuse.toml")?;
+//!
+//! let exporter = ExporterBuilder::from_config(&config)?.build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::constants::DEFAULT_LANGFUSE_HOST;
+use crate::credentials::{FileCredentialProvider, KeySource};
+use crate::error::BoxError;
+use crate::region::LangfuseRegion;
+use crate::secret::SecretString;
+use crate::tls::{self, TlsBackend, TlsConfig};
+use crate::{auth, endpoint, exporter, Compression, Error, Result};
+use opentelemetry_sdk::trace::{BatchConfig, BatchConfigBuilder};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Exporter configuration, typically deserialized from a configuration file.
+///
+/// Each key is given either inline (`public_key`) or as the path of a file
+/// holding it (`public_key_file`), e.g. a mounted secret. Key files are read
+/// again when they change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LangfuseConfig {
+    /// Base URL of the Langfuse instance, defaults to <https://cloud.langfuse.com>.
+    pub host: Option<String>,
+    /// Region of the Langfuse instance, `eu`, `us`, `hipaa` or a self-hosted
+    /// base URL. Used when `host` is not set, must match it otherwise.
+    pub region: Option<String>,
+    /// Regions the exporter may send to, see
+    /// [`ExporterBuilder::with_allowed_regions`](crate::ExporterBuilder::with_allowed_regions).
+    pub allowed_regions: Vec<String>,
+    /// Langfuse public key.
+    pub public_key: Option<String>,
+    /// Path of a file holding the Langfuse public key.
+    pub public_key_file: Option<PathBuf>,
+    /// Langfuse secret key, redacted from the `Debug` output.
+    pub secret_key: Option<SecretString>,
+    /// Path of a file holding the Langfuse secret key.
+    pub secret_key_file: Option<PathBuf>,
+    /// HTTP timeout of each export attempt, in milliseconds.
+    pub timeout_ms: Option<u64>,
+    /// Additional HTTP headers. An `Authorization` header makes the keys optional.
+    pub headers: HashMap<String, String>,
+    /// Compression of export requests, `gzip`, `zstd` or `none`.
+    pub compression: Option<String>,
+    /// TLS settings.
+    pub tls: TlsSettings,
+    /// Batching settings of the span processor, see [`LangfuseConfig::batch_config`].
+    pub batch: BatchSettings,
+}
+
+/// TLS settings of a [`LangfuseConfig`].
+///
+/// They require the `rustls-tls` or `native-tls` cargo feature.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSettings {
+    /// TLS implementation, `rustls` or `native-tls`.
+    pub backend: Option<String>,
+    /// Path of a PEM bundle of additional trusted root certificates.
+    pub ca_certificate: Option<PathBuf>,
+    /// Path of the PEM client certificate chain for mutual TLS.
+    pub client_certificate: Option<PathBuf>,
+    /// Path of the PEM (PKCS#8) client private key for mutual TLS.
+    pub client_key: Option<PathBuf>,
+    /// Minimum TLS version, `1.2` or `1.3`.
+    pub min_version: Option<String>,
+}
+
+/// Batching settings of a [`LangfuseConfig`].
+///
+/// Unset fields keep the defaults of the OpenTelemetry SDK.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchSettings {
+    /// Maximum number of spans buffered before new ones are dropped.
+    pub max_queue_size: Option<usize>,
+    /// Maximum number of spans per export.
+    pub max_export_batch_size: Option<usize>,
+    /// Delay between two exports, in milliseconds.
+    pub scheduled_delay_ms: Option<u64>,
+    /// Deadline of a single export, in milliseconds.
+    pub max_export_timeout_ms: Option<u64>,
+}
+
+impl LangfuseConfig {
+    /// Loads the configuration from a JSON or TOML file.
+    ///
+    /// Files with a `.toml` extension are parsed as TOML, which requires the
+    /// `toml` cargo feature. Other files are parsed as JSON. Read and parse
+    /// failures are reported as [`Error::ConfigFile`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the configuration file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let error = |source: BoxError| Error::ConfigFile {
+            path: path.to_path_buf(),
+            source,
+        };
+        let is_toml = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"));
+        if is_toml && cfg!(not(feature = "toml")) {
+            return Err(Error::FeatureNotEnabled("toml"));
+        }
+
+        let content = fs::read_to_string(path).map_err(|err| error(err.into()))?;
+        if is_toml {
+            #[cfg(feature = "toml")]
+            return toml::from_str(&content).map_err(|err| error(err.into()));
+        }
+        serde_json::from_str(&content).map_err(|err| error(err.into()))
+    }
+
+    /// Builds the batch configuration of the span processor.
+    ///
+    /// Pass it to [`InitConfig::with_batch_config`](crate::InitConfig::with_batch_config)
+    /// or to the builder of a batch span processor.
+    pub fn batch_config(&self) -> Result<BatchConfig> {
+        let batch = &self.batch;
+        let mut builder = BatchConfigBuilder::default();
+
+        let max_queue_size = positive(batch.max_queue_size, "batch.max_queue_size")?;
+        if let Some(size) = max_queue_size {
+            builder = builder.with_max_queue_size(size);
+        }
+        if let Some(size) = positive(batch.max_export_batch_size, "batch.max_export_batch_size")? {
+            if max_queue_size.is_some_and(|queue_size| size > queue_size) {
+                return Err(invalid(
+                    "batch.max_export_batch_size",
+                    "must not exceed batch.max_queue_size",
+                ));
+            }
+            builder = builder.with_max_export_batch_size(size);
+        }
+        if let Some(millis) = positive(batch.scheduled_delay_ms, "batch.scheduled_delay_ms")? {
+            builder = builder.with_scheduled_delay(Duration::from_millis(millis));
+        }
+        if let Some(millis) = positive(batch.max_export_timeout_ms, "batch.max_export_timeout_ms")?
+        {
+            builder = builder.with_max_export_timeout(Duration::from_millis(millis));
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Returns the OTLP endpoint of the configured host.
+    pub(crate) fn endpoint(&self) -> Result<String> {
+        let region = self
+            .region
+            .as_deref()
+            .map(|region| region.parse::<LangfuseRegion>())
+            .transpose()
+            .map_err(|err| invalid("region", err.to_string()))?;
+        let Some(host) = &self.host else {
+            let host = region
+                .as_ref()
+                .map_or(DEFAULT_LANGFUSE_HOST, LangfuseRegion::host);
+            return endpoint::try_build_otlp_endpoint(host);
+        };
+        let endpoint = endpoint::try_build_otlp_endpoint(host)
+            .map_err(|err| invalid("host", err.to_string()))?;
+        if let Some(region) = region {
+            if LangfuseRegion::of_endpoint(&endpoint) != LangfuseRegion::of_endpoint(region.host())
+            {
+                return Err(invalid(
+                    "region",
+                    format!("region {} does not match host {}", region, host),
+                ));
+            }
+        }
+        Ok(endpoint)
+    }
+
+    /// Returns the regions the exporter may send to.
+    pub(crate) fn allowed_regions(&self) -> Result<Vec<LangfuseRegion>> {
+        self.allowed_regions
+            .iter()
+            .map(|region| region.parse())
+            .collect::<Result<_>>()
+            .map_err(|err| invalid("allowed_regions", err.to_string()))
+    }
+
+    /// Returns the Authorization header built from the keys.
+    ///
+    /// Returns `None` if no key is configured but the headers hold an
+    /// Authorization header.
+    pub(crate) fn auth_header(&self) -> Result<Option<SecretString>> {
+        let public_key = key(
+            self.public_key.as_deref(),
+            self.public_key_file.as_deref(),
+            "public_key",
+            "public_key_file",
+        )?;
+        let secret_key = key(
+            self.secret_key.as_ref().map(SecretString::expose_secret),
+            self.secret_key_file.as_deref(),
+            "secret_key",
+            "secret_key_file",
+        )?;
+
+        match (public_key, secret_key) {
+            (Some(public_key), Some(secret_key)) => Ok(Some(SecretString::new(
+                auth::build_auth_header(public_key.expose_secret(), secret_key.expose_secret()),
+            ))),
+            (None, None)
+                if self
+                    .headers
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case("authorization")) =>
+            {
+                Ok(None)
+            }
+            (None, _) => Err(invalid(
+                "public_key",
+                "missing, set public_key or public_key_file",
+            )),
+            (_, None) => Err(invalid(
+                "secret_key",
+                "missing, set secret_key or secret_key_file",
+            )),
+        }
+    }
+
+    /// Returns a provider re-reading the key files when they change, if any
+    /// key is given as a file.
+    pub(crate) fn credential_provider(&self) -> Option<FileCredentialProvider> {
+        let source = |value: Option<&str>, path: &Option<PathBuf>| match (value, path) {
+            (_, Some(path)) => Some(KeySource::file(path)),
+            (Some(value), None) => Some(KeySource::Value(value.trim().into())),
+            (None, None) => None,
+        };
+        if self.public_key_file.is_none() && self.secret_key_file.is_none() {
+            return None;
+        }
+        Some(FileCredentialProvider::from_sources(
+            source(self.public_key.as_deref(), &self.public_key_file)?,
+            source(
+                self.secret_key.as_ref().map(SecretString::expose_secret),
+                &self.secret_key_file,
+            )?,
+        ))
+    }
+
+    /// Returns the per-attempt timeout.
+    pub(crate) fn timeout(&self) -> Result<Option<Duration>> {
+        Ok(positive(self.timeout_ms, "timeout_ms")?.map(Duration::from_millis))
+    }
+
+    /// Returns the additional headers, checking that they are valid HTTP headers
+    /// that the exporter does not set itself.
+    pub(crate) fn headers(&self) -> Result<HashMap<String, String>> {
+        exporter::header_map(&self.headers).map_err(|err| invalid("headers", err.to_string()))?;
+        Ok(self.headers.clone())
+    }
+
+    /// Returns the compression of export requests.
+    pub(crate) fn compression(&self) -> Result<Option<Compression>> {
+        let Some(value) = &self.compression else {
+            return Ok(None);
+        };
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(None),
+            other => other
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid("compression", format!("unknown compression {:?}", value))),
+        }
+    }
+
+    /// Returns the TLS settings, reading the certificate and key files.
+    pub(crate) fn tls_config(&self) -> Result<TlsConfig> {
+        let settings = &self.tls;
+        let mut config = TlsConfig::default();
+
+        if let Some(backend) = &settings.backend {
+            config.backend = Some(match backend.trim().to_ascii_lowercase().as_str() {
+                "rustls" | "rustls-tls" => TlsBackend::Rustls,
+                "native" | "native-tls" => TlsBackend::NativeTls,
+                _ => {
+                    return Err(invalid(
+                        "tls.backend",
+                        format!("unknown TLS backend {:?}", backend),
+                    ))
+                }
+            });
+        }
+
+        if let Some(path) = &settings.ca_certificate {
+            config
+                .ca_certificates
+                .push(read_file(path, "tls.ca_certificate")?);
+        }
+
+        config.client_identity = match (&settings.client_certificate, &settings.client_key) {
+            (Some(certificate), Some(key)) => Some((
+                read_file(certificate, "tls.client_certificate")?,
+                read_file(key, "tls.client_key")?,
+            )),
+            (Some(_), None) => {
+                return Err(invalid(
+                    "tls.client_key",
+                    "missing, required with tls.client_certificate",
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(invalid(
+                    "tls.client_certificate",
+                    "missing, required with tls.client_key",
+                ))
+            }
+            (None, None) => None,
+        };
+
+        if let Some(version) = &settings.min_version {
+            config.min_version = Some(tls::parse_tls_version(version).ok_or_else(|| {
+                invalid(
+                    "tls.min_version",
+                    format!("unknown TLS version {:?}", version),
+                )
+            })?);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Returns an [`Error::InvalidConfiguration`] for `field`.
+fn invalid(field: &'static str, message: impl Into<String>) -> Error {
+    Error::InvalidConfiguration {
+        field,
+        message: message.into(),
+    }
+}
+
+/// Rejects a zero value of `field`.
+fn positive<T: Default + PartialEq>(value: Option<T>, field: &'static str) -> Result<Option<T>> {
+    match value {
+        Some(value) if value == T::default() => Err(invalid(field, "must be greater than 0")),
+        value => Ok(value),
+    }
+}
+
+/// Returns a key given inline or as the path of a file holding it.
+fn key(
+    value: Option<&str>,
+    path: Option<&Path>,
+    field: &'static str,
+    file_field: &'static str,
+) -> Result<Option<SecretString>> {
+    let key = match (value, path) {
+        (Some(_), Some(_)) => {
+            return Err(invalid(
+                field,
+                format!("set either {} or {}, not both", field, file_field),
+            ))
+        }
+        (Some(value), None) => SecretString::new(value.trim()),
+        (None, Some(path)) => {
+            let content = SecretString::new(
+                String::from_utf8(read_file(path, file_field)?)
+                    .map_err(|_| invalid(file_field, format!("{} is not UTF-8", path.display())))?,
+            );
+            SecretString::new(content.expose_secret().trim())
+        }
+        (None, None) => return Ok(None),
+    };
+    if key.expose_secret().is_empty() {
+        let field = if value.is_some() { field } else { file_field };
+        return Err(invalid(field, "must not be empty"));
+    }
+    Ok(Some(key))
+}
+
+/// Reads the file at `path`, naming `field` on failure.
+fn read_file(path: &Path, field: &'static str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|err| invalid(field, format!("cannot read {}: {}", path.display(), err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn field_of(result: Result<impl std::fmt::Debug>) -> &'static str {
+        match result {
+            Err(Error::InvalidConfiguration { field, .. }) => field,
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults_match_from_env() {
+        let config = LangfuseConfig {
+            public_key: Some("pk-test".into()),
+            secret_key: Some("sk-test".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.endpoint().unwrap(),
+            "https://cloud.langfuse.com/api/public/otel/v1/traces"
+        );
+        assert_eq!(
+            config.auth_header().unwrap(),
+            Some(auth::build_auth_header("pk-test", "sk-test").into())
+        );
+        assert_eq!(config.timeout().unwrap(), None);
+        assert_eq!(config.compression().unwrap(), None);
+        assert!(config.tls_config().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_toml() {
+        let config: LangfuseConfig = toml::from_str(
+            r#"
+            region = "us"
+            allowed_regions = ["us", "hipaa"]
+            public_key = "pk-test"
+            secret_key = "sk-test"
+            timeout_ms = 2500
+            compression = "gzip"
+
+            [headers]
+            x-team = "core"
+
+            [tls]
+            min_version = "1.3"
+
+            [batch]
+            max_queue_size = 4096
+            max_export_batch_size = 256
+            scheduled_delay_ms = 1000
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.endpoint().unwrap(),
+            "https://us.cloud.langfuse.com/api/public/otel/v1/traces"
+        );
+        assert_eq!(
+            config.allowed_regions().unwrap(),
+            vec![LangfuseRegion::Us, LangfuseRegion::Hipaa]
+        );
+        assert_eq!(config.timeout().unwrap(), Some(Duration::from_millis(2500)));
+        assert_eq!(config.compression().unwrap(), Some(Compression::Gzip));
+        assert_eq!(config.headers().unwrap()["x-team"], "core");
+        assert_eq!(
+            config.tls_config().unwrap().min_version,
+            Some(crate::TlsVersion::Tls1_3)
+        );
+        // The fields of BatchConfig are private
+        let batch = format!("{:?}", config.batch_config().unwrap());
+        assert!(batch.contains("max_queue_size: 4096"));
+        assert!(batch.contains("max_export_batch_size: 256"));
+        assert!(batch.contains("scheduled_delay: 1s"));
+
+        let unknown = toml::from_str::<LangfuseConfig>("hots = \"https://x\"");
+        assert!(unknown.unwrap_err().to_string().contains("hots"));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        write!(
+            file,
+            r#"{{"host": "https://us.cloud.langfuse.com", "timeout_ms": 2500}}"#
+        )
+        .unwrap();
+        let config = LangfuseConfig::from_file(file.path()).unwrap();
+        assert_eq!(
+            config.host.as_deref(),
+            Some("https://us.cloud.langfuse.com")
+        );
+        assert_eq!(config.timeout_ms, Some(2500));
+
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        write!(file, r#"{{"hots": "https://x"}}"#).unwrap();
+        match LangfuseConfig::from_file(file.path()) {
+            Err(err @ Error::ConfigFile { .. }) => {
+                // The cause is reported by the source, not repeated in the message
+                assert!(!err.to_string().contains("hots"));
+                let source = std::error::Error::source(&err).unwrap();
+                assert!(source.to_string().contains("hots"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert!(matches!(
+            LangfuseConfig::from_file("missing.json"),
+            Err(Error::ConfigFile { .. })
+        ));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_file() {
+        let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        writeln!(file, "region = \"us\"").unwrap();
+        let config = LangfuseConfig::from_file(file.path()).unwrap();
+        assert_eq!(config.region.as_deref(), Some("us"));
+    }
+
+    #[test]
+    fn test_reads_key_files() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "sk-from-file").unwrap();
+        let config = LangfuseConfig {
+            public_key: Some("pk-test".into()),
+            secret_key_file: Some(file.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.auth_header().unwrap(),
+            Some(auth::build_auth_header("pk-test", "sk-from-file").into())
+        );
+    }
+
+    #[test]
+    fn test_errors_name_the_field() {
+        let keys = LangfuseConfig {
+            public_key: Some("pk-test".into()),
+            secret_key: Some("sk-test".into()),
+            ..Default::default()
+        };
+
+        let config = LangfuseConfig {
+            secret_key: None,
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.auth_header()), "secret_key");
+
+        let config = LangfuseConfig {
+            public_key_file: Some("/nonexistent/public-key".into()),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.auth_header()), "public_key");
+
+        let config = LangfuseConfig {
+            public_key: None,
+            public_key_file: Some("/nonexistent/public-key".into()),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.auth_header()), "public_key_file");
+
+        let config = LangfuseConfig {
+            host: Some("cloud.langfuse.com".into()),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.endpoint()), "host");
+
+        let config = LangfuseConfig {
+            host: Some("https://cloud.langfuse.com".into()),
+            region: Some("us".into()),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.endpoint()), "region");
+
+        let config = LangfuseConfig {
+            allowed_regions: vec!["eu".into(), "mars".into()],
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.allowed_regions()), "allowed_regions");
+
+        let config = LangfuseConfig {
+            timeout_ms: Some(0),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.timeout()), "timeout_ms");
+
+        let config = LangfuseConfig {
+            compression: Some("brotli".into()),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.compression()), "compression");
+
+        let config = LangfuseConfig {
+            headers: HashMap::from([("x team".into(), "core".into())]),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.headers()), "headers");
+
+        let config = LangfuseConfig {
+            headers: HashMap::from([("Content-Type".into(), "text/plain".into())]),
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.headers()), "headers");
+
+        let config = LangfuseConfig {
+            tls: TlsSettings {
+                min_version: Some("1.1".into()),
+                ..Default::default()
+            },
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.tls_config()), "tls.min_version");
+
+        let config = LangfuseConfig {
+            tls: TlsSettings {
+                client_certificate: Some("client.pem".into()),
+                ..Default::default()
+            },
+            ..keys.clone()
+        };
+        assert_eq!(field_of(config.tls_config()), "tls.client_key");
+
+        let config = LangfuseConfig {
+            batch: BatchSettings {
+                max_queue_size: Some(10),
+                max_export_batch_size: Some(100),
+                ..Default::default()
+            },
+            ..keys
+        };
+        assert_eq!(
+            field_of(config.batch_config()),
+            "batch.max_export_batch_size"
+        );
+    }
+
+    #[test]
+    fn test_authorization_header_replaces_keys() {
+        let config = LangfuseConfig {
+            headers: HashMap::from([("Authorization".into(), "Bearer token".into())]),
+            ..Default::default()
+        };
+
+        assert_eq!(config.auth_header().unwrap(), None);
+    }
+}
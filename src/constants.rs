@@ -6,8 +6,94 @@ pub const ENV_LANGFUSE_PUBLIC_KEY: &str = "LANGFUSE_PUBLIC_KEY";
 /// Environment variable name for the Langfuse secret key.
 pub const ENV_LANGFUSE_SECRET_KEY: &str = "LANGFUSE_SECRET_KEY";
 
+/// Environment variable name for the path of a file holding the Langfuse public key.
+///
+/// The file is re-read when it changes, see
+/// [`FileCredentialProvider`](crate::credentials::FileCredentialProvider).
+pub const ENV_LANGFUSE_PUBLIC_KEY_FILE: &str = "LANGFUSE_PUBLIC_KEY_FILE";
+
+/// Environment variable name for the path of a file holding the Langfuse secret key.
+///
+/// The file is re-read when it changes, see
+/// [`FileCredentialProvider`](crate::credentials::FileCredentialProvider).
+pub const ENV_LANGFUSE_SECRET_KEY_FILE: &str = "LANGFUSE_SECRET_KEY_FILE";
+
 /// Environment variable name for the Langfuse host URL.
 pub const ENV_LANGFUSE_HOST: &str = "LANGFUSE_HOST";
 
-/// Default Langfuse cloud host URL.
+/// Default Langfuse cloud host URL (EU region).
 pub const DEFAULT_LANGFUSE_HOST: &str = "https://cloud.langfuse.com";
+
+/// Langfuse cloud host URL of the US region.
+pub const US_LANGFUSE_HOST: &str = "https://us.cloud.langfuse.com";
+
+/// Langfuse cloud host URL of the HIPAA-compliant US region.
+pub const HIPAA_LANGFUSE_HOST: &str = "https://hipaa.cloud.langfuse.com";
+
+/// Environment variable name for the Langfuse region: `eu`, `us`, `hipaa` or
+/// the base URL of a self-hosted instance.
+///
+/// `LANGFUSE_HOST` takes precedence.
+pub const ENV_LANGFUSE_REGION: &str = "LANGFUSE_REGION";
+
+/// Environment variable name for the comma-separated list of regions the
+/// exporter may send to, e.g. `eu`.
+pub const ENV_LANGFUSE_ALLOWED_REGIONS: &str = "LANGFUSE_ALLOWED_REGIONS";
+
+/// Environment variable name for the fraction of traces to record, from `0.0`
+/// to `1.0`.
+///
+/// Applied by a trace ID ratio sampler, see
+/// [`ExporterBuilder::sampler`](crate::ExporterBuilder::sampler).
+pub const ENV_LANGFUSE_SAMPLE_RATE: &str = "LANGFUSE_SAMPLE_RATE";
+
+/// Environment variable name for switching tracing off (`false`) or on (`true`,
+/// the default).
+pub const ENV_LANGFUSE_TRACING_ENABLED: &str = "LANGFUSE_TRACING_ENABLED";
+
+/// Environment variable name for the export compression (`gzip` or `zstd`).
+pub const ENV_LANGFUSE_COMPRESSION: &str = "LANGFUSE_COMPRESSION";
+
+/// Standard OpenTelemetry variable for the base URL of all OTLP signals.
+///
+/// `/v1/traces` is appended to it. `LANGFUSE_HOST` takes precedence.
+pub const ENV_OTEL_EXPORTER_OTLP_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Standard OpenTelemetry variable for the complete OTLP traces endpoint URL.
+///
+/// `LANGFUSE_HOST` takes precedence.
+pub const ENV_OTEL_EXPORTER_OTLP_TRACES_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+
+/// Standard OpenTelemetry variable for the headers of all OTLP signals.
+pub const ENV_OTEL_EXPORTER_OTLP_HEADERS: &str = "OTEL_EXPORTER_OTLP_HEADERS";
+
+/// Standard OpenTelemetry variable for the headers of OTLP traces requests.
+pub const ENV_OTEL_EXPORTER_OTLP_TRACES_HEADERS: &str = "OTEL_EXPORTER_OTLP_TRACES_HEADERS";
+
+/// Standard OpenTelemetry variable for the timeout of all OTLP signals, in milliseconds.
+pub const ENV_OTEL_EXPORTER_OTLP_TIMEOUT: &str = "OTEL_EXPORTER_OTLP_TIMEOUT";
+
+/// Standard OpenTelemetry variable for the timeout of OTLP traces requests, in milliseconds.
+pub const ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT: &str = "OTEL_EXPORTER_OTLP_TRACES_TIMEOUT";
+
+/// Standard OpenTelemetry variable for the compression of all OTLP signals.
+///
+/// `LANGFUSE_COMPRESSION` takes precedence.
+pub const ENV_OTEL_EXPORTER_OTLP_COMPRESSION: &str = "OTEL_EXPORTER_OTLP_COMPRESSION";
+
+/// Standard OpenTelemetry variable for the compression of OTLP traces requests.
+///
+/// `LANGFUSE_COMPRESSION` takes precedence.
+pub const ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION: &str = "OTEL_EXPORTER_OTLP_TRACES_COMPRESSION";
+
+/// Environment variable name for the path of a PEM bundle of trusted root certificates.
+pub const ENV_LANGFUSE_CA_CERTIFICATE: &str = "LANGFUSE_CA_CERTIFICATE";
+
+/// Environment variable name for the path of the PEM client certificate (mutual TLS).
+pub const ENV_LANGFUSE_CLIENT_CERTIFICATE: &str = "LANGFUSE_CLIENT_CERTIFICATE";
+
+/// Environment variable name for the path of the PEM client private key (mutual TLS).
+pub const ENV_LANGFUSE_CLIENT_KEY: &str = "LANGFUSE_CLIENT_KEY";
+
+/// Environment variable name for the minimum TLS version (`1.2` or `1.3`).
+pub const ENV_LANGFUSE_TLS_MIN_VERSION: &str = "LANGFUSE_TLS_MIN_VERSION";
@@ -0,0 +1,485 @@
+//! Langfuse credentials resolved at export time.
+//!
+//! By default the exporter builds its Authorization header once, in
+//! [`ExporterBuilder::build`](crate::ExporterBuilder::build). A
+//! [`CredentialProvider`] set with
+//! [`ExporterBuilder::with_credential_provider`](crate::ExporterBuilder::with_credential_provider)
+//! is consulted for every export request instead, so rotated keys are picked
+//! up without a restart.
+//!
+//! Built-in providers:
+//!
+//! - [`Credentials`]: static keys
+//! - [`EnvCredentialProvider`]: `LANGFUSE_PUBLIC_KEY` and `LANGFUSE_SECRET_KEY`,
+//!   read on every call
+//! - [`FileCredentialProvider`]: files holding the keys, e.g. mounted secrets,
+//!   re-read when they change
+//! - [`FnCredentialProvider`]: an async closure, e.g. fetching the keys from Vault
+//!
+//! [`CachedCredentialProvider`] wraps any of them to reuse the keys for a
+//! time to live.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::credentials::{CachedCredentialProvider, Credentials, FnCredentialProvider};
+//! use opentelemetry_langfuse::ExporterBuilder;
+//! use std::time::Duration;
+//!
+//! # async fn fetch_from_vault() -> opentelemetry_langfuse::Result<(String, String)> { unimplemented!() }
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider = FnCredentialProvider::new(|| async {
+//!     let (public_key, secret_key) = fetch_from_vault().await?;
+//!     Ok(Credentials::new(public_key, secret_key))
+//! });
+//!
+//! let exporter = ExporterBuilder::new()
+//!     .with_host("https://cloud.langfuse.com")
+//!     .with_credential_provider(CachedCredentialProvider::new(
+//!         provider,
+//!         Duration::from_secs(300),
+//!     ))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::auth;
+use crate::constants::{
+    ENV_LANGFUSE_PUBLIC_KEY, ENV_LANGFUSE_PUBLIC_KEY_FILE, ENV_LANGFUSE_SECRET_KEY,
+    ENV_LANGFUSE_SECRET_KEY_FILE,
+};
+use crate::secret::SecretString;
+use crate::{Error, Result};
+use async_trait::async_trait;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A Langfuse key pair.
+///
+/// The secret key is zeroed on drop and redacted from the `Debug` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    public_key: String,
+    secret_key: SecretString,
+}
+
+impl Credentials {
+    /// Creates a key pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - The Langfuse public key
+    /// * `secret_key` - The Langfuse secret key
+    pub fn new(public_key: impl Into<String>, secret_key: impl Into<SecretString>) -> Self {
+        Self {
+            public_key: public_key.into(),
+            secret_key: secret_key.into(),
+        }
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// Returns the secret key.
+    pub fn secret_key(&self) -> &SecretString {
+        &self.secret_key
+    }
+
+    /// Returns the value of the Basic Authorization header.
+    pub fn auth_header(&self) -> SecretString {
+        auth::build_auth_header(&self.public_key, self.secret_key.expose_secret()).into()
+    }
+}
+
+/// Source of the Langfuse keys, consulted for every export request.
+///
+/// Implementations that are slow to query should be wrapped in a
+/// [`CachedCredentialProvider`].
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Returns the current key pair.
+    async fn credentials(&self) -> Result<Credentials>;
+}
+
+#[async_trait]
+impl CredentialProvider for Credentials {
+    async fn credentials(&self) -> Result<Credentials> {
+        Ok(self.clone())
+    }
+}
+
+#[async_trait]
+impl<P: CredentialProvider + ?Sized> CredentialProvider for Arc<P> {
+    async fn credentials(&self) -> Result<Credentials> {
+        (**self).credentials().await
+    }
+}
+
+/// Reads `LANGFUSE_PUBLIC_KEY` and `LANGFUSE_SECRET_KEY` on every call.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvCredentialProvider;
+
+impl EnvCredentialProvider {
+    /// Creates the provider.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for EnvCredentialProvider {
+    async fn credentials(&self) -> Result<Credentials> {
+        let public_key = env::var(ENV_LANGFUSE_PUBLIC_KEY)
+            .map_err(|_| Error::MissingEnvironmentVariable(ENV_LANGFUSE_PUBLIC_KEY))?;
+        let secret_key = env::var(ENV_LANGFUSE_SECRET_KEY)
+            .map_err(|_| Error::MissingEnvironmentVariable(ENV_LANGFUSE_SECRET_KEY))?;
+        Ok(Credentials::new(public_key, secret_key))
+    }
+}
+
+/// Reads the keys from files, e.g. Kubernetes secrets or files rendered by a
+/// Vault agent.
+///
+/// The files are read again whenever their modification time changes, so
+/// rotated keys are used by the next export. Surrounding whitespace is
+/// trimmed.
+#[derive(Debug)]
+pub struct FileCredentialProvider {
+    public_key: KeySource,
+    secret_key: KeySource,
+}
+
+/// A key given inline or as the path of a file holding it.
+#[derive(Debug)]
+pub(crate) enum KeySource {
+    Value(SecretString),
+    File {
+        path: PathBuf,
+        /// Modification time and content of the last read.
+        cached: Mutex<Option<(SystemTime, SecretString)>>,
+    },
+}
+
+impl KeySource {
+    /// Creates a source reading the file at `path`.
+    pub(crate) fn file(path: impl Into<PathBuf>) -> Self {
+        KeySource::File {
+            path: path.into(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Returns the key, reading the file again if it changed.
+    fn read(&self) -> Result<SecretString> {
+        let (path, cached) = match self {
+            KeySource::Value(value) => return Ok(value.clone()),
+            KeySource::File { path, cached } => (path, cached),
+        };
+
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|err| file_error(path, err))?;
+        let mut cached = cached.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some((cached_modified, key)) = cached.as_ref() {
+            if *cached_modified == modified {
+                return Ok(key.clone());
+            }
+        }
+
+        let content =
+            SecretString::new(fs::read_to_string(path).map_err(|err| file_error(path, err))?);
+        let key = SecretString::new(content.expose_secret().trim());
+        if key.expose_secret().is_empty() {
+            return Err(Error::Credentials {
+                message: format!("{} is empty", path.display()),
+                source: None,
+            });
+        }
+        *cached = Some((modified, key.clone()));
+        Ok(key)
+    }
+}
+
+impl FileCredentialProvider {
+    /// Creates a provider reading both keys from files.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key_path` - Path of the file holding the public key
+    /// * `secret_key_path` - Path of the file holding the secret key
+    pub fn new(public_key_path: impl Into<PathBuf>, secret_key_path: impl Into<PathBuf>) -> Self {
+        Self::from_sources(
+            KeySource::file(public_key_path),
+            KeySource::file(secret_key_path),
+        )
+    }
+
+    /// Creates a provider reading the secret key from a file, with a fixed
+    /// public key.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - The Langfuse public key
+    /// * `secret_key_path` - Path of the file holding the secret key
+    pub fn with_public_key(
+        public_key: impl Into<String>,
+        secret_key_path: impl Into<PathBuf>,
+    ) -> Self {
+        Self::from_sources(
+            KeySource::Value(public_key.into().into()),
+            KeySource::file(secret_key_path),
+        )
+    }
+
+    /// Creates a provider from `LANGFUSE_PUBLIC_KEY_FILE` and
+    /// `LANGFUSE_SECRET_KEY_FILE`.
+    ///
+    /// Each key falls back to `LANGFUSE_PUBLIC_KEY` or `LANGFUSE_SECRET_KEY`
+    /// when its file variable is not set, so only the secret key may be
+    /// mounted as a file.
+    pub fn from_env() -> Result<Self> {
+        let source = |file_name: &'static str, name: &'static str| {
+            if let Some(path) = env::var(file_name).ok().filter(|p| !p.trim().is_empty()) {
+                return Ok(KeySource::file(path.trim()));
+            }
+            env::var(name)
+                .map(|value| KeySource::Value(value.into()))
+                .map_err(|_| Error::MissingEnvironmentVariable(file_name))
+        };
+        Ok(Self::from_sources(
+            source(ENV_LANGFUSE_PUBLIC_KEY_FILE, ENV_LANGFUSE_PUBLIC_KEY)?,
+            source(ENV_LANGFUSE_SECRET_KEY_FILE, ENV_LANGFUSE_SECRET_KEY)?,
+        ))
+    }
+
+    pub(crate) fn from_sources(public_key: KeySource, secret_key: KeySource) -> Self {
+        Self {
+            public_key,
+            secret_key,
+        }
+    }
+
+    /// Reads the keys, from the cache if the files did not change.
+    pub(crate) fn read(&self) -> Result<Credentials> {
+        Ok(Credentials::new(
+            self.public_key.read()?.expose_secret(),
+            self.secret_key.read()?,
+        ))
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for FileCredentialProvider {
+    async fn credentials(&self) -> Result<Credentials> {
+        self.read()
+    }
+}
+
+/// Provides the keys returned by an async closure.
+pub struct FnCredentialProvider<F> {
+    f: F,
+}
+
+impl<F, Fut> FnCredentialProvider<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credentials>> + Send,
+{
+    /// Creates a provider calling `f` for the keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The async closure returning the current key pair
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F> fmt::Debug for FnCredentialProvider<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnCredentialProvider")
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<F, Fut> CredentialProvider for FnCredentialProvider<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credentials>> + Send,
+{
+    async fn credentials(&self) -> Result<Credentials> {
+        (self.f)().await
+    }
+}
+
+/// Reuses the keys of another provider for a time to live.
+///
+/// Failures are not cached, the next call queries the provider again.
+pub struct CachedCredentialProvider<P> {
+    provider: P,
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, Credentials)>>,
+}
+
+impl<P: CredentialProvider> CachedCredentialProvider<P> {
+    /// Creates a cache in front of `provider`.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider to query when the cache is empty or expired
+    /// * `ttl` - How long the keys are reused
+    pub fn new(provider: P, ttl: Duration) -> Self {
+        Self {
+            provider,
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl<P> fmt::Debug for CachedCredentialProvider<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedCredentialProvider")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<P: CredentialProvider> CredentialProvider for CachedCredentialProvider<P> {
+    async fn credentials(&self) -> Result<Credentials> {
+        {
+            let cached = self.cached.lock().unwrap_or_else(|err| err.into_inner());
+            if let Some((fetched, credentials)) = cached.as_ref() {
+                if fetched.elapsed() < self.ttl {
+                    return Ok(credentials.clone());
+                }
+            }
+        }
+
+        let credentials = self.provider.credentials().await?;
+        *self.cached.lock().unwrap_or_else(|err| err.into_inner()) =
+            Some((Instant::now(), credentials.clone()));
+        Ok(credentials)
+    }
+}
+
+/// Shared credential provider of an exporter.
+#[derive(Clone)]
+pub(crate) struct SharedCredentialProvider(pub(crate) Arc<dyn CredentialProvider>);
+
+impl fmt::Debug for SharedCredentialProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CredentialProvider")
+    }
+}
+
+fn file_error(path: &Path, err: std::io::Error) -> Error {
+    Error::Credentials {
+        message: format!("cannot read {}", path.display()),
+        source: Some(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_debug_redacts_secret_key() {
+        let debug = format!("{:?}", Credentials::new("pk-lf-1", "sk-lf-1"));
+
+        assert!(debug.contains("pk-lf-1"));
+        assert!(!debug.contains("sk-lf-1"));
+    }
+
+    #[tokio::test]
+    async fn test_file_provider_rereads_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret_path = dir.path().join("secret-key");
+        fs::write(&secret_path, "sk-lf-1\n").unwrap();
+        let provider = FileCredentialProvider::with_public_key("pk-lf-1", &secret_path);
+
+        assert_eq!(
+            provider.credentials().await.unwrap(),
+            Credentials::new("pk-lf-1", "sk-lf-1")
+        );
+
+        // Rotate the key, with a distinct modification time
+        fs::write(&secret_path, "sk-lf-2").unwrap();
+        let file = fs::File::options().write(true).open(&secret_path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        assert_eq!(
+            provider.credentials().await.unwrap(),
+            Credentials::new("pk-lf-1", "sk-lf-2")
+        );
+
+        fs::remove_file(&secret_path).unwrap();
+        match provider.credentials().await {
+            Err(
+                err @ Error::Credentials {
+                    source: Some(_), ..
+                },
+            ) => {
+                // The I/O error is reported by the source, not repeated in the message
+                assert!(!err.to_string().contains("No such file"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_provider_reuses_credentials_until_expired() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let provider = CachedCredentialProvider::new(
+            FnCredentialProvider::new(move || {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(Credentials::new("pk-lf-1", format!("sk-lf-{}", call))) }
+            }),
+            Duration::from_millis(50),
+        );
+
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .secret_key()
+                .expose_secret(),
+            "sk-lf-0"
+        );
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .secret_key()
+                .expose_secret(),
+            "sk-lf-0"
+        );
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .secret_key()
+                .expose_secret(),
+            "sk-lf-1"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
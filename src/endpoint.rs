@@ -1,12 +1,31 @@
 //! Endpoint URL utilities for Langfuse.
+//!
+//! Hosts and endpoints are parsed as URLs: they need an `http://` or
+//! `https://` scheme and a host, and must not contain a query string, a
+//! fragment or credentials. Invalid URLs are reported as
+//! [`Error::InvalidEndpoint`] rather than failing on the first export.
 
-use crate::constants::{DEFAULT_LANGFUSE_HOST, ENV_LANGFUSE_HOST};
+use crate::constants::{
+    DEFAULT_LANGFUSE_HOST, ENV_LANGFUSE_HOST, ENV_LANGFUSE_REGION, ENV_OTEL_EXPORTER_OTLP_ENDPOINT,
+    ENV_OTEL_EXPORTER_OTLP_TRACES_ENDPOINT,
+};
+use crate::region::{self, LangfuseRegion};
+use crate::{Error, Result};
 use std::env;
+use url::Url;
+
+/// Path of the Langfuse OTLP traces endpoint.
+const OTLP_TRACES_PATH: &str = "/api/public/otel/v1/traces";
+
+/// Path of the Langfuse ingestion API endpoint.
+const INGESTION_PATH: &str = "/api/public/ingestion";
 
 /// Builds the Langfuse OTLP endpoint URL by appending the API path.
 ///
 /// This function takes a base URL and appends "/api/public/otel/v1/traces" to create
-/// the full OTLP endpoint URL for Langfuse.
+/// the full OTLP endpoint URL for Langfuse. A sub-path of the base URL, e.g. of
+/// a reverse proxy, is kept. A URL that already ends with the API path is
+/// returned as-is.
 ///
 /// # Arguments
 ///
@@ -14,30 +33,181 @@ use std::env;
 ///
 /// # Returns
 ///
-/// Returns the complete OTLP endpoint URL.
+/// Returns the complete OTLP endpoint URL, or [`Error::InvalidEndpoint`] if
+/// `base_url` is not a valid `http://` or `https://` URL.
 ///
 /// # Example
 ///
 /// ```
-/// use opentelemetry_langfuse::endpoint::build_otlp_endpoint;
+/// use opentelemetry_langfuse::endpoint::try_build_otlp_endpoint;
 ///
-/// let endpoint = build_otlp_endpoint("https://cloud.langfuse.com");
+/// let endpoint = try_build_otlp_endpoint("https://cloud.langfuse.com").unwrap();
 /// assert_eq!(endpoint, "https://cloud.langfuse.com/api/public/otel/v1/traces");
+///
+/// let endpoint = try_build_otlp_endpoint("https://proxy.internal/langfuse/").unwrap();
+/// assert_eq!(endpoint, "https://proxy.internal/langfuse/api/public/otel/v1/traces");
+///
+/// assert!(try_build_otlp_endpoint("htps://cloud.langfuse.com").is_err());
 /// ```
+pub fn try_build_otlp_endpoint(base_url: &str) -> Result<String> {
+    append_path(base_url, OTLP_TRACES_PATH)
+}
+
+/// Builds the Langfuse OTLP endpoint URL by appending the API path.
+///
+/// Unlike [`try_build_otlp_endpoint`], an invalid `base_url` is not reported:
+/// the API path is appended to it as-is, and exports fail instead.
+///
+/// # Arguments
+///
+/// * `base_url` - The base Langfuse URL (e.g., `https://cloud.langfuse.com`)
+#[deprecated(note = "use `try_build_otlp_endpoint`, which rejects invalid URLs")]
 pub fn build_otlp_endpoint(base_url: &str) -> String {
-    let url = base_url.trim().trim_end_matches('/');
-    format!("{}/api/public/otel/v1/traces", url)
+    try_build_otlp_endpoint(base_url).unwrap_or_else(|_| {
+        format!(
+            "{}{}",
+            base_url.trim().trim_end_matches('/'),
+            OTLP_TRACES_PATH
+        )
+    })
+}
+
+/// Builds the Langfuse ingestion API endpoint URL by appending the API path.
+///
+/// This function takes a base URL and appends "/api/public/ingestion" to create
+/// the full endpoint URL of the Langfuse ingestion API, which is also available
+/// on self-hosted versions of Langfuse that predate the OTLP endpoint.
+///
+/// # Arguments
+///
+/// * `base_url` - The base Langfuse URL (e.g., `https://cloud.langfuse.com`)
+///
+/// # Returns
+///
+/// Returns the complete ingestion endpoint URL, or [`Error::InvalidEndpoint`]
+/// if `base_url` is not a valid `http://` or `https://` URL.
+///
+/// # Example
+///
+/// ```
+/// use opentelemetry_langfuse::endpoint::build_ingestion_endpoint;
+///
+/// let endpoint = build_ingestion_endpoint("https://cloud.langfuse.com").unwrap();
+/// assert_eq!(endpoint, "https://cloud.langfuse.com/api/public/ingestion");
+/// ```
+pub fn build_ingestion_endpoint(base_url: &str) -> Result<String> {
+    append_path(base_url, INGESTION_PATH)
+}
+
+/// Checks that a complete endpoint URL is valid, and returns it trimmed.
+pub(crate) fn validate_endpoint(endpoint: &str) -> Result<String> {
+    parse_url(endpoint)?;
+    Ok(endpoint.trim().to_string())
+}
+
+/// Checks that a base URL is valid, and returns it without trailing slashes.
+pub(crate) fn validate_base_url(base_url: &str) -> Result<String> {
+    parse_url(base_url)?;
+    Ok(base_url.trim().trim_end_matches('/').to_string())
+}
+
+/// Appends `path` to the path of `base_url`, unless it already ends with it.
+fn append_path(base_url: &str, path: &str) -> Result<String> {
+    let mut url = parse_url(base_url)?;
+    let base_path = url.path().trim_end_matches('/');
+    let full_path = if base_path.ends_with(path) {
+        base_path.to_string()
+    } else {
+        format!("{}{}", base_path, path)
+    };
+    url.set_path(&full_path);
+    Ok(url.into())
+}
+
+/// Parses a host or endpoint URL, rejecting URLs that cannot be exported to.
+fn parse_url(input: &str) -> Result<Url> {
+    let input = input.trim();
+    let invalid = |reason: String| Error::InvalidEndpoint {
+        url: input.to_string(),
+        reason,
+        source: None,
+    };
+
+    // Without a scheme, `localhost:3000` would parse with the scheme `localhost`
+    if !input.contains("://") {
+        return Err(invalid("missing http:// or https:// scheme".into()));
+    }
+    let url = Url::parse(input).map_err(|err| Error::InvalidEndpoint {
+        url: input.to_string(),
+        reason: "not a valid URL".into(),
+        source: Some(err),
+    })?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(format!(
+            "unsupported scheme {}://, expected http:// or https://",
+            url.scheme()
+        )));
+    }
+    if url.host().is_none() {
+        return Err(invalid("missing host".into()));
+    }
+    if url.query().is_some() {
+        return Err(invalid("query strings are not supported".into()));
+    }
+    if url.fragment().is_some() {
+        return Err(invalid("fragments are not supported".into()));
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(invalid(
+            "credentials must not be part of the URL, use the Langfuse keys".into(),
+        ));
+    }
+    Ok(url)
+}
+
+/// Returns the base URL of the Langfuse host of an endpoint URL.
+///
+/// The OTLP and ingestion API paths are stripped. Any other URL is assumed to
+/// be the base URL already.
+pub(crate) fn host_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim();
+    let base_url = endpoint
+        .strip_suffix(OTLP_TRACES_PATH)
+        .or_else(|| endpoint.strip_suffix(INGESTION_PATH))
+        .unwrap_or(endpoint);
+    base_url.trim_end_matches('/').to_string()
+}
+
+/// Returns the ingestion endpoint URL matching an endpoint URL.
+///
+/// An OTLP endpoint built by [`try_build_otlp_endpoint`] is mapped to the ingestion
+/// endpoint of the same host. Any other URL is assumed to be the complete
+/// ingestion endpoint already.
+pub(crate) fn ingestion_endpoint(endpoint: &str) -> Result<String> {
+    match endpoint.trim().strip_suffix(OTLP_TRACES_PATH) {
+        Some(base_url) => build_ingestion_endpoint(base_url),
+        None => validate_endpoint(endpoint),
+    }
 }
 
 /// Builds the Langfuse OTLP endpoint URL from environment variable.
 ///
 /// This function reads the LANGFUSE_HOST environment variable and creates
 /// the complete OTLP endpoint URL by appending "/api/public/otel/v1/traces".
-/// If LANGFUSE_HOST is not set, defaults to the cloud instance.
+///
+/// If LANGFUSE_HOST is not set, LANGFUSE_REGION selects the host of a region:
+/// `eu`, `us`, `hipaa` or the base URL of a self-hosted instance. When both are
+/// set, they must designate the same region.
+///
+/// If neither is set, the standard OpenTelemetry variables are used
+/// instead: OTEL_EXPORTER_OTLP_TRACES_ENDPOINT as the complete endpoint URL,
+/// or OTEL_EXPORTER_OTLP_ENDPOINT with "/v1/traces" appended. If none of them
+/// is set, defaults to the cloud instance.
 ///
 /// # Returns
 ///
-/// Returns the complete OTLP endpoint URL.
+/// Returns the complete OTLP endpoint URL, or [`Error::InvalidEndpoint`] if
+/// the configured URL is not valid.
 ///
 /// # Example
 ///
@@ -47,10 +217,37 @@ pub fn build_otlp_endpoint(base_url: &str) -> String {
 /// // Uses LANGFUSE_HOST env var if set, otherwise defaults to cloud
 /// let endpoint = build_otlp_endpoint_from_env().unwrap();
 /// ```
-pub fn build_otlp_endpoint_from_env() -> Result<String, crate::Error> {
-    let base_url =
-        env::var(ENV_LANGFUSE_HOST).unwrap_or_else(|_| DEFAULT_LANGFUSE_HOST.to_string());
-    Ok(build_otlp_endpoint(&base_url))
+pub fn build_otlp_endpoint_from_env() -> Result<String> {
+    let region = region::region_from_env()?;
+    if let Ok(base_url) = env::var(ENV_LANGFUSE_HOST) {
+        let endpoint = try_build_otlp_endpoint(&base_url)?;
+        // Both set, they must not contradict each other
+        if let Some(region) = region {
+            if LangfuseRegion::of_endpoint(&endpoint) != LangfuseRegion::of_endpoint(region.host())
+            {
+                return Err(Error::InvalidEnvironmentVariable {
+                    name: ENV_LANGFUSE_REGION,
+                    value: region.to_string(),
+                });
+            }
+        }
+        return Ok(endpoint);
+    }
+    if let Some(region) = region {
+        return try_build_otlp_endpoint(region.host());
+    }
+    if let Some(endpoint) = non_empty_env(ENV_OTEL_EXPORTER_OTLP_TRACES_ENDPOINT) {
+        return validate_endpoint(&endpoint);
+    }
+    if let Some(base_url) = non_empty_env(ENV_OTEL_EXPORTER_OTLP_ENDPOINT) {
+        return append_path(&base_url, "/v1/traces");
+    }
+    try_build_otlp_endpoint(DEFAULT_LANGFUSE_HOST)
+}
+
+/// Returns the value of an environment variable, unless unset or empty.
+fn non_empty_env(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
 }
 
 #[cfg(test)]
@@ -58,44 +255,138 @@ mod tests {
     use super::*;
     use serial_test::serial;
 
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_build_otlp_endpoint() {
+        assert_eq!(
+            build_otlp_endpoint("https://cloud.langfuse.com/"),
+            "https://cloud.langfuse.com/api/public/otel/v1/traces"
+        );
+        assert_eq!(
+            build_otlp_endpoint("localhost:3000"),
+            "localhost:3000/api/public/otel/v1/traces"
+        );
+    }
+
     #[test]
     fn test_build_otlp_endpoint() {
         // Test with URL without trailing slash
-        let endpoint = build_otlp_endpoint("https://cloud.langfuse.com");
+        let endpoint = try_build_otlp_endpoint("https://cloud.langfuse.com").unwrap();
         assert_eq!(
             endpoint,
             "https://cloud.langfuse.com/api/public/otel/v1/traces"
         );
 
         // Test with URL with trailing slash
-        let endpoint = build_otlp_endpoint("https://cloud.langfuse.com/");
+        let endpoint = try_build_otlp_endpoint("https://cloud.langfuse.com/").unwrap();
         assert_eq!(
             endpoint,
             "https://cloud.langfuse.com/api/public/otel/v1/traces"
         );
 
         // Test with US region URL
-        let endpoint = build_otlp_endpoint("https://us.cloud.langfuse.com");
+        let endpoint = try_build_otlp_endpoint("https://us.cloud.langfuse.com").unwrap();
         assert_eq!(
             endpoint,
             "https://us.cloud.langfuse.com/api/public/otel/v1/traces"
         );
 
         // Test with URL with trailing spaces (from env vars)
-        let endpoint = build_otlp_endpoint("  https://cloud.langfuse.com  ");
+        let endpoint = try_build_otlp_endpoint("  https://cloud.langfuse.com  ").unwrap();
         assert_eq!(
             endpoint,
             "https://cloud.langfuse.com/api/public/otel/v1/traces"
         );
 
         // Test with URL with trailing slash and spaces
-        let endpoint = build_otlp_endpoint("  https://cloud.langfuse.com/  ");
+        let endpoint = try_build_otlp_endpoint("  https://cloud.langfuse.com/  ").unwrap();
         assert_eq!(
             endpoint,
             "https://cloud.langfuse.com/api/public/otel/v1/traces"
         );
     }
 
+    #[test]
+    fn test_build_otlp_endpoint_keeps_paths() {
+        // Sub-path of a reverse proxy
+        assert_eq!(
+            try_build_otlp_endpoint("https://proxy.internal/langfuse/").unwrap(),
+            "https://proxy.internal/langfuse/api/public/otel/v1/traces"
+        );
+
+        // Already complete, the path is not appended twice
+        assert_eq!(
+            try_build_otlp_endpoint("https://cloud.langfuse.com/api/public/otel/v1/traces/")
+                .unwrap(),
+            "https://cloud.langfuse.com/api/public/otel/v1/traces"
+        );
+
+        // Ports are kept
+        assert_eq!(
+            try_build_otlp_endpoint("http://localhost:3000").unwrap(),
+            "http://localhost:3000/api/public/otel/v1/traces"
+        );
+    }
+
+    #[test]
+    fn test_build_otlp_endpoint_rejects_invalid_urls() {
+        for (url, reason) in [
+            ("cloud.langfuse.com", "missing http:// or https:// scheme"),
+            ("localhost:3000", "missing http:// or https:// scheme"),
+            ("htps://cloud.langfuse.com", "unsupported scheme htps://"),
+            ("https://cloud.langfuse.com?region=eu", "query strings"),
+            ("https://cloud.langfuse.com#eu", "fragments"),
+            ("https://pk:sk@cloud.langfuse.com", "credentials"),
+            ("https://", "not a valid URL"),
+        ] {
+            match try_build_otlp_endpoint(url) {
+                Err(Error::InvalidEndpoint {
+                    url: rejected,
+                    reason: actual,
+                    ..
+                }) => {
+                    assert_eq!(rejected, url);
+                    assert!(actual.contains(reason), "{}: {}", url, actual);
+                }
+                other => panic!("{} was not rejected: {:?}", url, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_ingestion_endpoint() {
+        let endpoint = build_ingestion_endpoint("  https://cloud.langfuse.com/  ").unwrap();
+        assert_eq!(endpoint, "https://cloud.langfuse.com/api/public/ingestion");
+    }
+
+    #[test]
+    fn test_ingestion_endpoint() {
+        // OTLP endpoints are mapped to the ingestion endpoint of the same host
+        let endpoint =
+            ingestion_endpoint("https://langfuse.internal/api/public/otel/v1/traces").unwrap();
+        assert_eq!(endpoint, "https://langfuse.internal/api/public/ingestion");
+
+        // Other endpoints are used as-is
+        let endpoint = ingestion_endpoint("https://proxy.internal/langfuse/ingestion").unwrap();
+        assert_eq!(endpoint, "https://proxy.internal/langfuse/ingestion");
+    }
+
+    #[test]
+    fn test_host_url() {
+        assert_eq!(
+            host_url("https://cloud.langfuse.com/api/public/otel/v1/traces"),
+            "https://cloud.langfuse.com"
+        );
+        assert_eq!(
+            host_url("https://proxy.internal/langfuse/api/public/ingestion"),
+            "https://proxy.internal/langfuse"
+        );
+        assert_eq!(
+            host_url("https://langfuse.internal/"),
+            "https://langfuse.internal"
+        );
+    }
+
     #[test]
     #[serial]
     fn test_build_otlp_endpoint_from_env() {
@@ -118,6 +409,42 @@ mod tests {
         env::remove_var(ENV_LANGFUSE_HOST);
     }
 
+    #[test]
+    #[serial]
+    fn test_otel_endpoint_variables() {
+        env::remove_var(ENV_LANGFUSE_HOST);
+
+        env::set_var(
+            ENV_OTEL_EXPORTER_OTLP_ENDPOINT,
+            "https://langfuse.internal/api/public/otel/",
+        );
+        assert_eq!(
+            build_otlp_endpoint_from_env().unwrap(),
+            "https://langfuse.internal/api/public/otel/v1/traces"
+        );
+
+        // The signal-specific endpoint is used as-is and wins
+        env::set_var(
+            ENV_OTEL_EXPORTER_OTLP_TRACES_ENDPOINT,
+            "https://traces.internal/custom",
+        );
+        assert_eq!(
+            build_otlp_endpoint_from_env().unwrap(),
+            "https://traces.internal/custom"
+        );
+
+        // LANGFUSE_HOST takes precedence over both
+        env::set_var(ENV_LANGFUSE_HOST, "https://cloud.langfuse.com");
+        assert_eq!(
+            build_otlp_endpoint_from_env().unwrap(),
+            "https://cloud.langfuse.com/api/public/otel/v1/traces"
+        );
+
+        env::remove_var(ENV_LANGFUSE_HOST);
+        env::remove_var(ENV_OTEL_EXPORTER_OTLP_ENDPOINT);
+        env::remove_var(ENV_OTEL_EXPORTER_OTLP_TRACES_ENDPOINT);
+    }
+
     #[test]
     #[serial]
     fn test_default_langfuse_host() {
@@ -1,8 +1,33 @@
 //! Error types for the opentelemetry-langfuse library.
+//!
+//! Errors caused by the configuration carry a [`hint`](Error::hint) telling
+//! what to change, and the underlying error as their
+//! [`source`](std::error::Error::source) where there is one.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::ExporterBuilder;
+//!
+//! match ExporterBuilder::from_env().and_then(|builder| builder.build()) {
+//!     Ok(exporter) => { /* install the exporter */ }
+//!     Err(err) => {
+//!         eprintln!("Cannot export traces to Langfuse: {}", err);
+//!         if let Some(hint) = err.hint() {
+//!             eprintln!("hint: {}", hint);
+//!         }
+//!     }
+//! }
+//! ```
 
+use opentelemetry_sdk::error::OTelSdkError;
 use opentelemetry_sdk::trace::TraceError;
+use std::path::PathBuf;
 use thiserror::Error;
 
+/// Underlying error of an [`Error`](enum@Error) whose causes have different types.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Error type for opentelemetry-langfuse operations.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -10,18 +35,264 @@ pub enum Error {
     #[error("Missing environment variable: {0}")]
     MissingEnvironmentVariable(&'static str),
 
+    /// Environment variable has an invalid value.
+    #[error("Invalid value {value:?} for environment variable {name}")]
+    InvalidEnvironmentVariable {
+        /// Name of the environment variable.
+        name: &'static str,
+        /// The rejected value.
+        value: String,
+    },
+
     /// Required configuration is missing.
     #[error("Missing configuration: {0}")]
     MissingConfiguration(&'static str),
 
+    /// Neither Langfuse keys nor an Authorization header are configured.
+    #[error("Missing Langfuse credentials: no key pair, credential provider or Authorization header is configured")]
+    MissingCredentials,
+
+    /// The Langfuse keys are malformed, e.g. swapped or padded with whitespace.
+    #[error("Invalid Langfuse credentials: {reason}")]
+    InvalidCredentialFormat {
+        /// Description of the problem.
+        reason: String,
+        /// The underlying error, e.g. of decoding an Authorization header.
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// An HTTP header name is invalid.
+    #[error("Invalid HTTP header name {name:?}")]
+    InvalidHeaderName {
+        /// The rejected header name.
+        name: String,
+        /// The underlying error.
+        #[source]
+        source: http::header::InvalidHeaderName,
+    },
+
+    /// The value of an HTTP header is invalid, e.g. because it contains a newline.
+    #[error("Invalid value for HTTP header {name:?}")]
+    InvalidHeaderValue {
+        /// Name of the header.
+        name: String,
+        /// The underlying error.
+        #[source]
+        source: http::header::InvalidHeaderValue,
+    },
+
+    /// A custom HTTP header would override one set by the exporter.
+    #[error("HTTP header {0:?} is set by the exporter and cannot be overridden")]
+    ReservedHeader(String),
+
+    /// A configuration file could not be read or parsed.
+    #[error("Cannot load configuration file {path:?}")]
+    ConfigFile {
+        /// Path of the configuration file.
+        path: PathBuf,
+        /// The underlying I/O or parse error.
+        #[source]
+        source: BoxError,
+    },
+
+    /// The exporter needs a Tokio runtime or a blocking HTTP client.
+    #[error("No Tokio runtime is running and the exporter has no blocking HTTP client")]
+    MissingRuntime,
+
+    /// A field of a [`LangfuseConfig`](crate::LangfuseConfig) has an invalid value.
+    #[error("Invalid configuration field `{field}`: {message}")]
+    InvalidConfiguration {
+        /// Path of the field, e.g. `tls.min_version`.
+        field: &'static str,
+        /// Description of the problem.
+        message: String,
+    },
+
+    /// A host or endpoint URL is not a valid Langfuse URL.
+    #[error("Invalid endpoint URL {url:?}: {reason}")]
+    InvalidEndpoint {
+        /// The rejected URL.
+        url: String,
+        /// Why the URL was rejected.
+        reason: String,
+        /// The URL parse error, if the URL could not be parsed at all.
+        #[source]
+        source: Option<url::ParseError>,
+    },
+
+    /// A region name could not be parsed.
+    #[error("Unknown Langfuse region {0:?}, expected eu, us, hipaa or a base URL")]
+    InvalidRegion(String),
+
+    /// The endpoint does not belong to an allowed region.
+    #[error("Endpoint {endpoint} is in region {region}, which is not allowed")]
+    RegionNotAllowed {
+        /// The rejected endpoint.
+        endpoint: String,
+        /// Region of the endpoint.
+        region: String,
+    },
+
+    /// A cargo feature required by the configuration is not enabled.
+    #[error("Cargo feature `{0}` must be enabled for this configuration")]
+    FeatureNotEnabled(&'static str),
+
+    /// A credential provider failed to provide the Langfuse keys.
+    #[error("Cannot obtain Langfuse credentials: {message}")]
+    Credentials {
+        /// Description of the problem.
+        message: String,
+        /// The underlying error, e.g. of reading a key file.
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// The TLS configuration is invalid, e.g. a certificate could not be parsed.
+    #[error("Invalid TLS configuration: {message}")]
+    Tls {
+        /// Description of the problem.
+        message: String,
+        /// The underlying I/O or TLS error.
+        #[source]
+        source: Option<BoxError>,
+    },
+
     /// OpenTelemetry trace error.
     #[error("OpenTelemetry error: {0}")]
     OpenTelemetry(#[from] TraceError),
 
+    /// I/O error, e.g. while opening the spool directory.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A span file could not be parsed.
+    #[error("Invalid span file {path:?} at line {line}: {message}")]
+    InvalidSpanFile {
+        /// Path of the span file.
+        path: PathBuf,
+        /// Line number of the invalid record, starting at 1.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+
+    /// Exporting spans failed.
+    #[error("Export failed: {0}")]
+    Export(#[from] OTelSdkError),
+
     /// OTLP exporter build error.
     #[error("OTLP exporter error: {0}")]
     OtlpExporter(#[from] opentelemetry_otlp::ExporterBuildError),
 }
 
+impl Error {
+    /// Returns what to change to fix the error, if it is caused by the
+    /// configuration.
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            Error::MissingEnvironmentVariable(name) => format!(
+                "Set {}, or configure the exporter with ExporterBuilder::new()",
+                name
+            ),
+            Error::InvalidEnvironmentVariable { name, .. } => {
+                format!(
+                    "Fix or unset {}, see the crate documentation for its format",
+                    name
+                )
+            }
+            Error::MissingConfiguration(what) => {
+                format!("Set the {} on the ExporterBuilder", what)
+            }
+            Error::MissingCredentials => "Set LANGFUSE_PUBLIC_KEY and LANGFUSE_SECRET_KEY, \
+                or call ExporterBuilder::with_basic_auth(), with the keys from the API keys \
+                page of the Langfuse project settings"
+                .into(),
+            Error::InvalidCredentialFormat { .. } => "Copy the public key (pk-lf-...) and the \
+                secret key (sk-lf-...) again from the API keys page of the Langfuse project \
+                settings, without surrounding whitespace"
+                .into(),
+            Error::InvalidHeaderName { .. } => "Header names may only contain ASCII letters, \
+                digits and !#$%&'*+-.^_`|~"
+                .into(),
+            Error::InvalidHeaderValue { name, .. } => format!(
+                "Remove newlines and other control characters from the value of {}",
+                name
+            ),
+            Error::ReservedHeader(name) => format!(
+                "Remove the {} header, the exporter sets it from the protocol and compression",
+                name
+            ),
+            Error::ConfigFile { .. } => "Check the syntax of the file against LangfuseConfig, \
+                unknown fields are rejected"
+                .into(),
+            Error::MissingRuntime => "Call the function within a Tokio runtime, or enable the \
+                blocking-client feature and use ExporterBuilder::with_blocking_http_client()"
+                .into(),
+            Error::InvalidConfiguration { field, .. } => {
+                format!("Fix `{}` in the configuration", field)
+            }
+            Error::InvalidEndpoint { .. } => "Use the base URL of the Langfuse instance, \
+                e.g. https://cloud.langfuse.com or https://langfuse.example.com/langfuse"
+                .into(),
+            Error::InvalidRegion(_) => {
+                "Use eu, us, hipaa or the http(s) base URL of a self-hosted instance".into()
+            }
+            Error::RegionNotAllowed { .. } => "Point LANGFUSE_HOST to an allowed region, \
+                or add the region to the allowlist"
+                .into(),
+            Error::FeatureNotEnabled(feature) => format!(
+                "Enable the `{}` feature of opentelemetry-langfuse in Cargo.toml",
+                feature
+            ),
+            Error::Credentials { .. } => "Check that the credential provider can reach its \
+                source, e.g. that the key files exist and are readable"
+                .into(),
+            Error::Tls { .. } => "Check that the certificate and key files exist and are \
+                PEM-encoded, with the client key in PKCS#8 format"
+                .into(),
+            Error::InvalidSpanFile { .. } => "Only upload files written by the FileExporter".into(),
+            Error::OpenTelemetry(_) | Error::Io(_) | Error::Export(_) | Error::OtlpExporter(_) => {
+                return None
+            }
+        };
+        Some(hint)
+    }
+}
+
 /// Result type alias for opentelemetry-langfuse operations.
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configuration_errors_have_hints() {
+        let err = Error::FeatureNotEnabled("gzip-http");
+        assert_eq!(
+            err.hint().unwrap(),
+            "Enable the `gzip-http` feature of opentelemetry-langfuse in Cargo.toml"
+        );
+        assert!(Error::MissingCredentials
+            .hint()
+            .unwrap()
+            .contains("LANGFUSE_PUBLIC_KEY"));
+
+        let err = Error::Io(std::io::Error::other("disk full"));
+        assert!(err.hint().is_none());
+    }
+
+    #[test]
+    fn test_header_errors_keep_their_source() {
+        let source = http::HeaderValue::try_from("core\n").unwrap_err();
+        let err = Error::InvalidHeaderValue {
+            name: "x-team".into(),
+            source,
+        };
+
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(err.to_string().contains("x-team"));
+        assert!(err.hint().unwrap().contains("x-team"));
+    }
+}
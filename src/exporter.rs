@@ -1,23 +1,186 @@
 //! Langfuse OTLP exporter configuration.
 //!
-//! This module provides the [`ExporterBuilder`] for configuring an OpenTelemetry
-//! OTLP exporter that sends traces to Langfuse.
+//! This module provides the [`ExporterBuilder`] for configuring the
+//! [`LangfuseExporter`], an OpenTelemetry OTLP exporter that sends traces to Langfuse.
 //!
 //! See the [Langfuse OpenTelemetry documentation](https://langfuse.com/integrations/native/opentelemetry)
 //! for more details about the integration.
 
+use crate::batch;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::config::LangfuseConfig;
+use crate::constants::{
+    ENV_LANGFUSE_COMPRESSION, ENV_LANGFUSE_PUBLIC_KEY_FILE, ENV_LANGFUSE_SAMPLE_RATE,
+    ENV_LANGFUSE_SECRET_KEY_FILE, ENV_LANGFUSE_TRACING_ENABLED, ENV_OTEL_EXPORTER_OTLP_COMPRESSION,
+    ENV_OTEL_EXPORTER_OTLP_HEADERS, ENV_OTEL_EXPORTER_OTLP_TIMEOUT,
+    ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION, ENV_OTEL_EXPORTER_OTLP_TRACES_HEADERS,
+    ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT,
+};
+use crate::credentials::{CredentialProvider, FileCredentialProvider, SharedCredentialProvider};
+use crate::ingestion::IngestionExporter;
+use crate::metrics::{self, ExporterMetrics};
+use crate::otlp::OtlpExporter;
+use crate::region::{self, LangfuseRegion};
+use crate::retry::RetryPolicy;
+use crate::secret::SecretString;
+use crate::spool::{Spool, SpoolConfig};
+use crate::tls::{TlsBackend, TlsConfig, TlsVersion};
+use crate::transport::{self, LangfuseHttpClient};
+use crate::verify::{self, Verification, VerifyError};
 use crate::{auth, endpoint, Error, Result};
-use opentelemetry_otlp::{SpanExporter, WithExportConfig, WithHttpConfig};
+use http::header::AUTHORIZATION;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::global;
+use opentelemetry::metrics::{Meter, MeterProvider};
+use opentelemetry_otlp::{Compression, Protocol as OtlpProtocol};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Sampler, SpanData};
+use opentelemetry_sdk::Resource;
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
-/// Builder for configuring a Langfuse OTLP exporter.
+/// Wire format of OTLP/HTTP export requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    /// Binary protobuf payloads (`application/x-protobuf`).
+    #[default]
+    HttpBinary,
+    /// JSON payloads (`application/json`).
+    ///
+    /// Requires the `http-json` cargo feature.
+    HttpJson,
+}
+
+impl From<Protocol> for OtlpProtocol {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::HttpBinary => OtlpProtocol::HttpBinary,
+            Protocol::HttpJson => OtlpProtocol::HttpJson,
+        }
+    }
+}
+
+/// API used to send spans to Langfuse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// The OTLP endpoint (`/api/public/otel/v1/traces`).
+    #[default]
+    Otlp,
+    /// The ingestion API (`/api/public/ingestion`).
+    ///
+    /// Spans are mapped to `trace-create`, `span-create`, `generation-create`
+    /// and `event-create` events. Use this for self-hosted Langfuse versions
+    /// that do not provide the OTLP endpoint yet.
+    Ingestion,
+}
+
+/// Span exporter sending traces to Langfuse.
+///
+/// Batches whose encoded size exceeds the configured limit are split into
+/// several requests, and single spans that are too large on their own have
+/// their largest string attributes truncated, so that Langfuse does not reject
+/// the whole batch with `413 Payload Too Large`.
+///
+/// Created with [`ExporterBuilder::build`]. With tracing
+/// [disabled](ExporterBuilder::with_tracing_enabled), it drops every span.
+#[derive(Debug)]
+pub struct LangfuseExporter {
+    inner: Backend,
+    max_batch_size: usize,
+    /// Encoded size of the resource sent with every request.
+    resource_size: usize,
+    /// Encoding of the spans in request bodies.
+    encoding: batch::Encoding,
+    circuit_breaker: Option<CircuitBreaker>,
+    metrics: ExporterMetrics,
+}
+
+/// Exporter of the configured [`Transport`].
+// Built once per pipeline, so the size of the variants does not matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+enum Backend {
+    Otlp(OtlpExporter),
+    Ingestion(IngestionExporter),
+    /// Tracing is disabled, spans are dropped.
+    Disabled,
+}
+
+impl LangfuseExporter {
+    /// Returns the circuit breaker of the exporter, if one is configured.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_ref()
+    }
+}
+
+impl opentelemetry_sdk::trace::SpanExporter for LangfuseExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        if let Backend::Disabled = self.inner {
+            return Ok(());
+        }
+
+        // Export every chunk, even if one of them fails
+        let mut result = Ok(());
+        for chunk in batch::split_batch(
+            batch,
+            self.resource_size,
+            self.max_batch_size,
+            self.encoding,
+        ) {
+            let spans = chunk.len();
+            let (chunk_result, status) = transport::track_export(spans, async {
+                match &self.inner {
+                    Backend::Otlp(exporter) => exporter.export(chunk).await,
+                    Backend::Ingestion(exporter) => exporter.export(chunk).await,
+                    Backend::Disabled => Ok(()),
+                }
+            })
+            .await;
+            self.metrics
+                .record_export(spans, chunk_result.is_ok(), status);
+            result = result.and(chunk_result);
+        }
+        result
+    }
+
+    // Requests are sent within `export`, so there is nothing to flush or shut down
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource_size = batch::resource_size(resource, self.encoding);
+        match &mut self.inner {
+            Backend::Otlp(exporter) => exporter.set_resource(resource),
+            Backend::Ingestion(exporter) => exporter.set_resource(resource),
+            Backend::Disabled => {}
+        }
+    }
+}
+
+/// Builder for configuring a Langfuse exporter.
+///
+/// Its `Debug` and `Display` output hold no secrets, so the configuration can
+/// be logged.
 pub struct ExporterBuilder {
     endpoint: Option<String>,
-    auth_header: Option<String>,
+    auth_header: Option<SecretString>,
+    credential_provider: Option<SharedCredentialProvider>,
     timeout: Option<Duration>,
     additional_headers: HashMap<String, String>,
-    http_client: Option<reqwest::Client>,
+    http_client: Option<transport::Client>,
+    tls: TlsConfig,
+    retry_policy: Option<RetryPolicy>,
+    spool: Option<SpoolConfig>,
+    compression: Option<Compression>,
+    protocol: Protocol,
+    transport: Transport,
+    max_batch_size: usize,
+    circuit_breaker: Option<CircuitBreaker>,
+    meter: Option<Meter>,
+    allowed_regions: Vec<LangfuseRegion>,
+    sample_rate: Option<f64>,
+    tracing_enabled: bool,
 }
 
 impl ExporterBuilder {
@@ -26,9 +189,22 @@ impl ExporterBuilder {
         Self {
             endpoint: None,
             auth_header: None,
+            credential_provider: None,
             timeout: None,
             additional_headers: HashMap::new(),
             http_client: None,
+            tls: TlsConfig::default(),
+            retry_policy: None,
+            spool: None,
+            compression: None,
+            protocol: Protocol::HttpBinary,
+            transport: Transport::Otlp,
+            max_batch_size: batch::DEFAULT_MAX_BATCH_SIZE,
+            circuit_breaker: None,
+            meter: None,
+            allowed_regions: Vec::new(),
+            sample_rate: None,
+            tracing_enabled: true,
         }
     }
 
@@ -36,7 +212,8 @@ impl ExporterBuilder {
     ///
     /// # Arguments
     ///
-    /// * `endpoint` - The complete OTLP endpoint URL
+    /// * `endpoint` - The complete OTLP endpoint URL, or the complete ingestion
+    ///   endpoint URL with [`Transport::Ingestion`]
     pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
         self.endpoint = Some(endpoint.into());
         self
@@ -48,7 +225,8 @@ impl ExporterBuilder {
     ///
     /// * `auth_header` - The complete authentication header value
     pub fn with_auth_header(mut self, auth_header: impl Into<String>) -> Self {
-        self.auth_header = Some(auth_header.into());
+        self.auth_header = Some(SecretString::new(auth_header));
+        self.credential_provider = None;
         self
     }
 
@@ -59,22 +237,161 @@ impl ExporterBuilder {
     /// * `public_key` - The Langfuse public key
     /// * `secret_key` - The Langfuse secret key
     pub fn with_basic_auth(mut self, public_key: &str, secret_key: &str) -> Self {
-        self.auth_header = Some(auth::build_auth_header(public_key, secret_key));
+        self.auth_header = Some(SecretString::new(auth::build_auth_header(
+            public_key, secret_key,
+        )));
+        self.credential_provider = None;
+        self
+    }
+
+    /// Sets a provider consulted for the Langfuse keys on every export request.
+    ///
+    /// Unlike [`with_basic_auth`](Self::with_basic_auth), rotated keys are
+    /// picked up without rebuilding the exporter. Replaces the credentials of
+    /// a previous `with_basic_auth` or `with_auth_header` call, and vice versa.
+    /// Wrap slow providers in a
+    /// [`CachedCredentialProvider`](crate::credentials::CachedCredentialProvider).
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The credential provider
+    pub fn with_credential_provider(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.credential_provider = Some(SharedCredentialProvider(Arc::new(provider)));
+        self.auth_header = None;
         self
     }
 
     /// Sets the Langfuse host URL.
     ///
+    /// A sub-path, e.g. of a reverse proxy, is kept. An invalid URL makes
+    /// [`build`](Self::build) fail with [`Error::InvalidEndpoint`].
+    ///
     /// # Arguments
     ///
     /// * `host` - The base Langfuse URL (e.g., `https://cloud.langfuse.com`)
     pub fn with_host(mut self, host: &str) -> Self {
-        self.endpoint = Some(endpoint::build_otlp_endpoint(host));
+        // An invalid host is kept as-is, build() rejects it with the same error
+        let endpoint =
+            endpoint::try_build_otlp_endpoint(host).unwrap_or_else(|_| host.trim().into());
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// Sets the Langfuse host to the one of a region.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - The Langfuse cloud region, or a self-hosted instance
+    pub fn with_region(self, region: LangfuseRegion) -> Self {
+        self.with_host(region.host())
+    }
+
+    /// Restricts the regions the exporter may send to.
+    ///
+    /// [`build`](Self::build) fails with [`Error::RegionNotAllowed`] if the
+    /// final endpoint does not belong to one of them, whether it was set with
+    /// [`with_region`](Self::with_region), [`with_host`](Self::with_host),
+    /// [`with_endpoint`](Self::with_endpoint) or the environment. Self-hosted
+    /// regions match on their base URL. By default every region is allowed.
+    ///
+    /// # Arguments
+    ///
+    /// * `regions` - The allowed regions
+    pub fn with_allowed_regions(
+        mut self,
+        regions: impl IntoIterator<Item = LangfuseRegion>,
+    ) -> Self {
+        self.allowed_regions = regions.into_iter().collect();
+        self
+    }
+
+    /// Sets the fraction of traces to record, from `0.0` to `1.0`.
+    ///
+    /// The rate is applied by the [`sampler`](Self::sampler), which
+    /// [`init_with`](crate::init_with) installs on the tracer provider. All
+    /// traces are recorded by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The sample rate
+    pub fn with_sample_rate(mut self, rate: f64) -> Self {
+        self.sample_rate = Some(rate);
+        self
+    }
+
+    /// Enables or disables tracing.
+    ///
+    /// A disabled builder needs no credentials: [`build`](Self::build) returns
+    /// an exporter that drops every span, the [`sampler`](Self::sampler)
+    /// records no span, and [`init_with`](crate::init_with) installs a tracer
+    /// provider without span processor. Enabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether spans are recorded and exported
+    pub fn with_tracing_enabled(mut self, enabled: bool) -> Self {
+        self.tracing_enabled = enabled;
         self
     }
 
+    /// Returns whether tracing is enabled.
+    pub fn tracing_enabled(&self) -> bool {
+        self.tracing_enabled
+    }
+
+    /// Returns the sampler of the tracer provider matching the configuration.
+    ///
+    /// `AlwaysOff` when tracing is disabled, a trace ID ratio sampler when a
+    /// sample rate is set, `AlwaysOn` otherwise, as in the other Langfuse SDKs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidConfiguration`] if tracing is enabled and the
+    /// sample rate is not between `0.0` and `1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use opentelemetry_langfuse::ExporterBuilder;
+    /// use opentelemetry_sdk::trace::{BatchSpanProcessor, SdkTracerProvider};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Honours LANGFUSE_SAMPLE_RATE and LANGFUSE_TRACING_ENABLED
+    /// let builder = ExporterBuilder::from_env()?;
+    /// let sampler = builder.sampler()?;
+    ///
+    /// let provider = SdkTracerProvider::builder()
+    ///     .with_sampler(sampler)
+    ///     .with_span_processor(BatchSpanProcessor::builder(builder.build()?).build())
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sampler(&self) -> Result<Sampler> {
+        if !self.tracing_enabled {
+            return Ok(Sampler::AlwaysOff);
+        }
+        Ok(match self.checked_sample_rate()? {
+            Some(rate) => Sampler::TraceIdRatioBased(rate),
+            None => Sampler::AlwaysOn,
+        })
+    }
+
+    /// Returns the sample rate, checking that it is between `0.0` and `1.0`.
+    fn checked_sample_rate(&self) -> Result<Option<f64>> {
+        match self.sample_rate {
+            Some(rate) if !(0.0..=1.0).contains(&rate) => Err(Error::InvalidConfiguration {
+                field: "sample_rate",
+                message: format!("{} is not between 0.0 and 1.0", rate),
+            }),
+            rate => Ok(rate),
+        }
+    }
+
     /// Sets the HTTP timeout for the exporter.
     ///
+    /// When a retry policy is configured, the timeout applies to each attempt.
+    ///
     /// # Arguments
     ///
     /// * `timeout` - The timeout duration
@@ -93,18 +410,240 @@ impl ExporterBuilder {
     ///
     /// * `client` - The HTTP client to use
     pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
-        self.http_client = Some(client);
+        self.http_client = Some(client.into());
+        self
+    }
+
+    /// Sets a blocking HTTP client for the exporter.
+    ///
+    /// The exporter then sends requests from the calling thread and needs no
+    /// async runtime, so it can be used with the thread-based
+    /// [`BatchSpanProcessor`](opentelemetry_sdk::trace::BatchSpanProcessor) and
+    /// [`SimpleSpanProcessor`](opentelemetry_sdk::trace::SimpleSpanProcessor)
+    /// of the SDK. As with any `reqwest::blocking::Client`, the exporter must
+    /// not be used or dropped from within an async runtime.
+    ///
+    /// Requires the `blocking-client` cargo feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The blocking HTTP client to use
+    #[cfg(feature = "blocking-client")]
+    pub fn with_blocking_http_client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.http_client = Some(client.into());
+        self
+    }
+
+    /// Sets the TLS implementation of the HTTP client.
+    ///
+    /// Defaults to `native-tls` if that feature is enabled, and to `rustls-tls`
+    /// otherwise. The selected backend requires its cargo feature, otherwise
+    /// [`build`](Self::build) returns an error.
+    ///
+    /// Like all TLS settings, this cannot be combined with a custom HTTP client
+    /// set by [`with_http_client`](Self::with_http_client).
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The TLS implementation to use
+    pub fn with_tls_backend(mut self, backend: TlsBackend) -> Self {
+        self.tls.backend = Some(backend);
+        self
+    }
+
+    /// Adds trusted root certificates, e.g. of an internal certificate authority.
+    ///
+    /// The certificates are trusted in addition to the default root
+    /// certificates of the TLS backend. Requires the `rustls-tls` or
+    /// `native-tls` cargo feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `pem` - One or more PEM-encoded certificates
+    pub fn with_ca_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.ca_certificates.push(pem.into());
+        self
+    }
+
+    /// Sets the client certificate presented to Langfuse (mutual TLS).
+    ///
+    /// Requires the `rustls-tls` or `native-tls` cargo feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `certificate_pem` - The PEM-encoded client certificate chain
+    /// * `key_pem` - The PEM-encoded PKCS#8 private key of the certificate
+    pub fn with_client_identity_pem(
+        mut self,
+        certificate_pem: impl Into<Vec<u8>>,
+        key_pem: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.tls.client_identity = Some((certificate_pem.into(), key_pem.into()));
+        self
+    }
+
+    /// Sets the minimum TLS version accepted when connecting to Langfuse.
+    ///
+    /// Requires the `rustls-tls` or `native-tls` cargo feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The minimum TLS version
+    pub fn with_tls_min_version(mut self, version: TlsVersion) -> Self {
+        self.tls.min_version = Some(version);
+        self
+    }
+
+    /// Sets the retry policy for failed exports.
+    ///
+    /// By default, failed exports are not retried. With a retry policy, requests
+    /// that fail with a transient error (`429`, `5xx`, connection resets,
+    /// timeouts) are retried with exponential backoff, honouring `Retry-After`.
+    /// Requests rejected with `400`, `401` or `403` are never retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The retry policy to apply
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Sets a durable on-disk spool for batches that could not be exported.
+    ///
+    /// Batches that still fail with a transient error after all retries are
+    /// written to the spool directory instead of being dropped. They are
+    /// replayed in order once Langfuse is reachable again, including batches
+    /// left behind by a previous run of the process. The export of a spooled
+    /// batch is still reported as failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The spool configuration
+    pub fn with_spool(mut self, config: SpoolConfig) -> Self {
+        self.spool = Some(config);
+        self
+    }
+
+    /// Sets a circuit breaker for exports that cannot succeed.
+    ///
+    /// After a number of consecutive `401`, `403` or `404` responses, e.g.
+    /// because the keys were rotated or the host is wrong, the circuit opens and
+    /// exports fail immediately without contacting Langfuse. A probe request is
+    /// sent on a slow schedule and closes the circuit once it is accepted.
+    ///
+    /// Keep a clone of the breaker to observe its [state](CircuitBreaker::state).
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit_breaker` - The circuit breaker to use
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Sets the meter provider receiving the self-telemetry metrics of the exporter.
+    ///
+    /// The exporter records the number of exported and failed spans, retries
+    /// and bytes sent, as well as histograms of the export latency and request
+    /// size labelled by HTTP status class (`http.status_class`). All
+    /// instruments are prefixed with `langfuse.exporter.`.
+    ///
+    /// Defaults to the global meter provider at the time the exporter is built.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The meter provider to use
+    pub fn with_meter_provider(mut self, provider: &impl MeterProvider) -> Self {
+        self.meter = Some(metrics::meter(provider));
+        self
+    }
+
+    /// Sets the compression applied to export requests.
+    ///
+    /// Requires the `gzip-http` or `zstd-http` cargo feature for the selected
+    /// algorithm, otherwise [`build`](Self::build) returns an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `compression` - The compression algorithm
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Sets the wire format of export requests.
+    ///
+    /// Defaults to [`Protocol::HttpBinary`]. [`Protocol::HttpJson`] requires the
+    /// `http-json` cargo feature, otherwise [`build`](Self::build) returns an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol` - The protocol to use
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the API used to send spans to Langfuse.
+    ///
+    /// Defaults to [`Transport::Otlp`]. With [`Transport::Ingestion`], the
+    /// endpoint set by [`with_host`](Self::with_host) or
+    /// [`from_env`](Self::from_env) is mapped to the ingestion API of the same
+    /// host, and the protocol setting does not apply. The ingestion API does
+    /// not take compressed requests, so [`build`](Self::build) fails if a
+    /// compression is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The transport to use
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the maximum encoded size of a single export request, in bytes.
+    ///
+    /// Batches above this size are split into several requests. A span that
+    /// exceeds the limit on its own has its largest string attributes truncated
+    /// until it fits, and is marked with a `...[truncated]` suffix. The size is
+    /// measured on the uncompressed protobuf encoding. Defaults to 4 MiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The maximum request size in bytes
+    pub fn with_max_batch_size(mut self, bytes: usize) -> Self {
+        self.max_batch_size = bytes;
         self
     }
 
     /// Adds an additional HTTP header.
     ///
+    /// Replaces a header of the same name, compared case-insensitively.
+    /// [`build`](Self::build) fails with [`Error::InvalidHeaderName`] or
+    /// [`Error::InvalidHeaderValue`] if the header is not valid HTTP, and with
+    /// [`Error::ReservedHeader`] for headers set by the exporter, such as
+    /// `Content-Type`.
+    ///
+    /// An `Authorization` header is kept as a secret, like the one of
+    /// [`with_auth_header`](Self::with_auth_header): it replaces the
+    /// credentials of a previous `with_auth_header`, `with_basic_auth` or
+    /// `with_credential_provider` call, and vice versa.
+    ///
     /// # Arguments
     ///
     /// * `name` - The header name
     /// * `value` - The header value
     pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.additional_headers.insert(name.into(), value.into());
+        let name = name.into();
+        if name.eq_ignore_ascii_case(AUTHORIZATION.as_str()) {
+            self.auth_header = Some(SecretString::new(value));
+            self.credential_provider = None;
+            return self;
+        }
+        self.additional_headers
+            .retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+        self.additional_headers.insert(name, value.into());
         self
     }
 
@@ -113,23 +652,58 @@ impl ExporterBuilder {
     /// # Arguments
     ///
     /// * `headers` - An iterator of header name-value pairs
-    pub fn with_headers<I, K, V>(mut self, headers: I) -> Self
+    pub fn with_headers<I, K, V>(self, headers: I) -> Self
     where
         I: IntoIterator<Item = (K, V)>,
         K: Into<String>,
         V: Into<String>,
     {
-        self.additional_headers
-            .extend(headers.into_iter().map(|(k, v)| (k.into(), v.into())));
-        self
+        headers
+            .into_iter()
+            .fold(self, |builder, (k, v)| builder.with_header(k, v))
     }
 
     /// Creates an ExporterBuilder from environment variables.
     ///
     /// This method reads Langfuse-specific variables:
     /// - `LANGFUSE_HOST`: The base URL of your Langfuse instance (defaults to <https://cloud.langfuse.com>)
+    /// - `LANGFUSE_REGION`: `eu`, `us`, `hipaa` or a self-hosted base URL, used when
+    ///   `LANGFUSE_HOST` is not set (optional)
+    /// - `LANGFUSE_ALLOWED_REGIONS`: Comma-separated regions the exporter may send to,
+    ///   see [`with_allowed_regions`](Self::with_allowed_regions) (optional)
     /// - `LANGFUSE_PUBLIC_KEY`: Your Langfuse public key (required)
     /// - `LANGFUSE_SECRET_KEY`: Your Langfuse secret key (required)
+    /// - `LANGFUSE_PUBLIC_KEY_FILE` / `LANGFUSE_SECRET_KEY_FILE`: Paths of files
+    ///   holding the keys, in place of the variables above. They are re-read
+    ///   when they change, see [`FileCredentialProvider`] (optional)
+    /// - `LANGFUSE_COMPRESSION`: Compression of export requests, `gzip` or `zstd` (optional)
+    /// - `LANGFUSE_CA_CERTIFICATE`: Path of a PEM bundle of trusted root certificates (optional)
+    /// - `LANGFUSE_CLIENT_CERTIFICATE`: Path of the PEM client certificate for mutual TLS (optional)
+    /// - `LANGFUSE_CLIENT_KEY`: Path of the PEM client private key for mutual TLS (optional)
+    /// - `LANGFUSE_TLS_MIN_VERSION`: Minimum TLS version, `1.2` or `1.3` (optional)
+    /// - `LANGFUSE_SAMPLE_RATE`: Fraction of traces to record, from `0.0` to `1.0`,
+    ///   see [`with_sample_rate`](Self::with_sample_rate) (optional)
+    /// - `LANGFUSE_TRACING_ENABLED`: `false` disables tracing, see
+    ///   [`with_tracing_enabled`](Self::with_tracing_enabled). No other variable
+    ///   is read then, so the keys may be unset (optional)
+    ///
+    /// The standard OpenTelemetry variables are read as well, so exporters of
+    /// pods configured for any OTLP backend keep working:
+    /// - `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` / `OTEL_EXPORTER_OTLP_ENDPOINT`:
+    ///   Endpoint, used when `LANGFUSE_HOST` is not set
+    /// - `OTEL_EXPORTER_OTLP_TRACES_HEADERS` / `OTEL_EXPORTER_OTLP_HEADERS`:
+    ///   Additional headers as `name=value` pairs separated by commas, with
+    ///   percent-encoded values. An `Authorization` header among them makes the
+    ///   Langfuse keys optional.
+    /// - `OTEL_EXPORTER_OTLP_TRACES_TIMEOUT` / `OTEL_EXPORTER_OTLP_TIMEOUT`:
+    ///   Timeout in milliseconds
+    /// - `OTEL_EXPORTER_OTLP_TRACES_COMPRESSION` / `OTEL_EXPORTER_OTLP_COMPRESSION`:
+    ///   Compression, used when `LANGFUSE_COMPRESSION` is not set
+    ///
+    /// Settings are resolved in this order, the first one wins: explicit
+    /// builder calls, `LANGFUSE_*` variables, `OTEL_EXPORTER_OTLP_TRACES_*`
+    /// variables, `OTEL_EXPORTER_OTLP_*` variables. The Authorization header
+    /// built from the Langfuse keys replaces one from the OTEL headers.
     ///
     /// # Example
     ///
@@ -146,112 +720,547 @@ impl ExporterBuilder {
     /// # }
     /// ```
     pub fn from_env() -> Result<Self> {
+        // Nothing else is read while tracing is disabled
+        if !tracing_enabled_from_env()? {
+            return Ok(Self::new().with_tracing_enabled(false));
+        }
+        let sample_rate = sample_rate_from_env()?;
+
         // Get Langfuse endpoint (defaults to cloud if not set)
         let langfuse_endpoint = endpoint::build_otlp_endpoint_from_env()?;
 
-        // Get Langfuse credentials
-        let auth = auth::build_auth_header_from_env()?;
-
-        Ok(Self {
+        let mut headers = headers_from_env()?;
+        let builder = Self {
             endpoint: Some(langfuse_endpoint),
-            auth_header: Some(auth),
-            timeout: None,
-            additional_headers: HashMap::new(),
-            http_client: None,
+            auth_header: take_authorization(&mut headers),
+            timeout: timeout_from_env()?,
+            additional_headers: headers,
+            compression: compression_from_env()?,
+            tls: TlsConfig::from_env()?,
+            allowed_regions: region::allowed_regions_from_env()?,
+            sample_rate,
+            ..Self::new()
+        };
+
+        // Key files are re-read on change, so they need a credential provider
+        let key_files = [ENV_LANGFUSE_PUBLIC_KEY_FILE, ENV_LANGFUSE_SECRET_KEY_FILE]
+            .iter()
+            .any(|name| env::var(name).is_ok_and(|path| !path.trim().is_empty()));
+        if key_files {
+            let provider = FileCredentialProvider::from_env()?;
+            // Fail now rather than on the first export
+            provider.read()?;
+            return Ok(builder.with_credential_provider(provider));
+        }
+
+        // Get Langfuse credentials, unless the OTEL headers already authorize
+        match auth::build_auth_header_from_env() {
+            Ok(auth) => Ok(Self {
+                auth_header: Some(SecretString::from(auth)),
+                ..builder
+            }),
+            Err(Error::MissingEnvironmentVariable(_)) if builder.auth_header.is_some() => {
+                Ok(builder)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Creates an ExporterBuilder from a [`LangfuseConfig`], e.g. loaded from a
+    /// configuration file.
+    ///
+    /// Unset fields have the same defaults as the unset variables of
+    /// [`from_env`](Self::from_env); environment variables are not read.
+    /// Invalid fields are reported as [`Error::InvalidConfiguration`], naming
+    /// the field.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The exporter configuration
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use opentelemetry_langfuse::{ExporterBuilder, LangfuseConfig};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config: LangfuseConfig = toml::from_str(
+    ///     r#"
+    ///     public_key = "pk-lf-..."
+    ///     secret_key = "sk-lf-..."
+    ///     compression = "none"
+    ///     "#,
+    /// )?;
+    /// let exporter = ExporterBuilder::from_config(&config)?.build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_config(config: &LangfuseConfig) -> Result<Self> {
+        let mut headers = config.headers()?;
+        let header_auth = take_authorization(&mut headers);
+        let builder = Self {
+            endpoint: Some(config.endpoint()?),
+            auth_header: config.auth_header()?.or(header_auth),
+            timeout: config.timeout()?,
+            additional_headers: headers,
+            compression: config.compression()?,
+            tls: config.tls_config()?,
+            allowed_regions: config.allowed_regions()?,
+            ..Self::new()
+        };
+
+        // Key files are re-read on change, so they need a credential provider
+        Ok(match config.credential_provider() {
+            Some(provider) => builder.with_credential_provider(provider),
+            None => builder,
         })
     }
 
-    /// Builds the Langfuse OTLP exporter.
+    /// Returns whether requests are sent with a blocking HTTP client.
+    pub(crate) fn uses_blocking_client(&self) -> bool {
+        #[cfg(feature = "blocking-client")]
+        if let Some(transport::Client::Blocking(_)) = self.http_client {
+            return true;
+        }
+        false
+    }
+
+    /// Checks that Langfuse is reachable and accepts the configured credentials.
     ///
-    /// # Returns
+    /// Calls the health and project endpoints of the configured host, so that
+    /// a wrong host, region or key pair is detected before any span is
+    /// exported. [`build`](Self::build) does not contact Langfuse.
+    ///
+    /// Like [`build`](Self::build), it rejects hosts outside the
+    /// [allowed regions](Self::with_allowed_regions). When a cloud region
+    /// rejects the keys, they are tried against the other cloud regions
+    /// named in the allowlist to report [`VerifyError::WrongRegion`]. Without
+    /// an allowlist, the keys are never sent to another region.
     ///
-    /// Returns a Result containing the configured SpanExporter if successful.
-    pub fn build(self) -> Result<SpanExporter> {
+    /// With a [blocking HTTP client](Self::with_blocking_http_client), the
+    /// returned future blocks and must not be awaited within an async runtime.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use opentelemetry_langfuse::{ExporterBuilder, VerifyError};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let builder = ExporterBuilder::from_env()?;
+    /// match builder.verify().await {
+    ///     Ok(verification) => println!("exporting to {:?}", verification.project_name),
+    ///     Err(VerifyError::WrongRegion { region_host, .. }) => {
+    ///         eprintln!("set LANGFUSE_HOST={}", region_host)
+    ///     }
+    ///     Err(err) => eprintln!("Langfuse is not usable: {}", err),
+    /// }
+    /// let exporter = builder.build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn verify(&self) -> std::result::Result<Verification, VerifyError> {
         let endpoint = self
             .endpoint
+            .as_deref()
             .ok_or(Error::MissingConfiguration("endpoint"))?;
+        let authorization = match &self.credential_provider {
+            Some(provider) => provider.0.credentials().await?.auth_header(),
+            None => self.auth_header.clone().ok_or(Error::MissingCredentials)?,
+        };
 
-        // Create headers map
-        let mut headers = HashMap::new();
+        let endpoint = endpoint::validate_endpoint(endpoint)?;
+        region::check_allowed(&endpoint, &self.allowed_regions)?;
+        verify::verify(
+            &self.http_client()?,
+            &endpoint::host_url(&endpoint),
+            authorization.expose_secret(),
+            self.timeout.unwrap_or(verify::DEFAULT_TIMEOUT),
+            &verify::cloud_hosts(&self.allowed_regions),
+        )
+        .await
+    }
 
-        // Add additional headers first (may include Authorization from OTEL env)
-        headers.extend(self.additional_headers);
+    /// Returns the HTTP client to send requests with.
+    fn http_client(&self) -> Result<transport::Client> {
+        match &self.http_client {
+            Some(_) if !self.tls.is_empty() => Err(Error::Tls {
+                message: "TLS settings cannot be applied to a custom HTTP client".into(),
+                source: None,
+            }),
+            Some(client) => Ok(client.clone()),
+            None if self.tls.is_empty() => Ok(transport::Client::default()),
+            None => Ok(self.tls.build_client()?.into()),
+        }
+    }
 
-        // Handle Authorization header with proper precedence:
-        // 1. auth_header (from with_auth_header/with_basic_auth) takes precedence
-        // 2. Otherwise use authorization from additional_headers (normalized)
-        // 3. Error if neither is present
+    /// Builds the Langfuse exporter.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the configured LangfuseExporter if successful.
+    pub fn build(self) -> Result<LangfuseExporter> {
+        // The sample rate does not matter while tracing is disabled
+        if self.tracing_enabled {
+            self.checked_sample_rate()?;
+        }
 
-        if let Some(auth_header) = self.auth_header {
-            // Remove any existing authorization headers (case-insensitive)
-            // since auth_header takes precedence
-            let auth_keys: Vec<String> = headers
-                .keys()
-                .filter(|k| k.eq_ignore_ascii_case("authorization"))
-                .cloned()
-                .collect();
+        let encoding = match (self.transport, self.protocol) {
+            (Transport::Ingestion, _) => batch::Encoding::Ingestion,
+            (Transport::Otlp, Protocol::HttpJson) => batch::Encoding::Json,
+            (Transport::Otlp, Protocol::HttpBinary) => batch::Encoding::Protobuf,
+        };
 
-            for key in auth_keys {
-                headers.remove(&key);
-            }
+        // Nothing is sent, so nothing else needs to be valid
+        if !self.tracing_enabled {
+            let meter = self
+                .meter
+                .unwrap_or_else(|| metrics::meter(global::meter_provider().as_ref()));
+            return Ok(LangfuseExporter {
+                inner: Backend::Disabled,
+                max_batch_size: self.max_batch_size,
+                resource_size: batch::resource_size(&Resource::builder_empty().build(), encoding),
+                encoding,
+                circuit_breaker: None,
+                metrics: ExporterMetrics::new(&meter),
+            });
+        }
 
-            // Insert the auth_header with normalized key
-            headers.insert("Authorization".to_string(), auth_header);
-        } else {
-            // No explicit auth_header, check if we have one in additional_headers
-            let has_auth = headers
-                .keys()
-                .any(|k| k.eq_ignore_ascii_case("authorization"));
-
-            if has_auth {
-                // Find and normalize the Authorization header key
-                let auth_keys: Vec<String> = headers
-                    .keys()
-                    .filter(|k| k.eq_ignore_ascii_case("authorization"))
-                    .cloned()
-                    .collect();
-
-                // If we have authorization with non-standard casing, normalize it
-                for key in auth_keys {
-                    if key != "Authorization" {
-                        if let Some(value) = headers.remove(&key) {
-                            headers.insert("Authorization".to_string(), value);
-                        }
-                    }
-                }
-            } else {
-                // No Authorization header found anywhere
-                return Err(Error::MissingConfiguration(
-                    "Authorization header or Langfuse credentials",
-                ));
+        let endpoint = self
+            .endpoint
+            .clone()
+            .ok_or(Error::MissingConfiguration("endpoint"))?;
+
+        // Without the feature, the OTLP exporter silently falls back to protobuf
+        if self.protocol == Protocol::HttpJson && cfg!(not(feature = "http-json")) {
+            return Err(Error::FeatureNotEnabled("http-json"));
+        }
+        if let (Transport::Ingestion, Some(compression)) = (self.transport, self.compression) {
+            return Err(Error::InvalidConfiguration {
+                field: "compression",
+                message: format!(
+                    "{} compression is not supported by the ingestion transport",
+                    compression
+                ),
+            });
+        }
+        match self.compression {
+            Some(Compression::Gzip) if cfg!(not(feature = "gzip-http")) => {
+                return Err(Error::FeatureNotEnabled("gzip-http"));
             }
+            Some(Compression::Zstd) if cfg!(not(feature = "zstd-http")) => {
+                return Err(Error::FeatureNotEnabled("zstd-http"));
+            }
+            _ => {}
         }
 
-        // Build HTTP config with client
-        let http_client = self.http_client.unwrap_or_default();
+        let endpoint = match self.transport {
+            Transport::Otlp => endpoint::validate_endpoint(&endpoint)?,
+            Transport::Ingestion => endpoint::ingestion_endpoint(&endpoint)?,
+        };
+        region::check_allowed(&endpoint, &self.allowed_regions)?;
 
-        let mut http_config = SpanExporter::builder()
-            .with_http()
-            .with_http_client(http_client)
-            .with_endpoint(endpoint)
-            .with_headers(headers);
+        let client = self.http_client()?;
 
-        // Apply timeout if configured
-        if let Some(timeout) = self.timeout {
-            http_config = http_config.with_timeout(timeout);
-        }
+        let headers = self.additional_headers;
 
-        // Create OTLP exporter
-        Ok(http_config.build()?)
-    }
-}
+        // A credential provider sets the Authorization header on each request,
+        // otherwise the transport sets the configured one
+        let authorization = match (&self.credential_provider, self.auth_header) {
+            (Some(_), _) => None,
+            (None, Some(authorization)) => Some(authorization),
+            (None, None) => return Err(Error::MissingCredentials),
+        };
 
-impl Default for ExporterBuilder {
-    fn default() -> Self {
+        let spool = self.spool.map(Spool::open).transpose()?;
+        let meter = self
+            .meter
+            .unwrap_or_else(|| metrics::meter(global::meter_provider().as_ref()));
+        let metrics = ExporterMetrics::new(&meter);
+
+        // Build HTTP config with client, wrapped in our transport which applies
+        // the per-attempt timeout, the retry policy, the spool, the circuit breaker
+        // and the request metrics, and sets the resolved headers on every request
+        let mut header_map = header_map(&headers)?;
+        if let Some(authorization) = authorization {
+            auth::check_auth_header(authorization.expose_secret())?;
+            let mut value =
+                HeaderValue::try_from(authorization.expose_secret()).map_err(|source| {
+                    Error::InvalidHeaderValue {
+                        name: AUTHORIZATION.to_string(),
+                        source,
+                    }
+                })?;
+            value.set_sensitive(true);
+            header_map.insert(AUTHORIZATION, value);
+        }
+        let http_client = LangfuseHttpClient::new(client)
+            .with_headers(header_map)
+            .with_credential_provider(self.credential_provider)
+            .with_timeout(self.timeout)
+            .with_retry_policy(self.retry_policy)
+            .with_spool(spool)
+            .with_circuit_breaker(self.circuit_breaker.clone())
+            .with_metrics(Some(metrics.clone()));
+
+        if self.transport == Transport::Ingestion {
+            return Ok(LangfuseExporter {
+                inner: Backend::Ingestion(IngestionExporter::new(http_client, endpoint, headers)),
+                max_batch_size: self.max_batch_size,
+                resource_size: batch::resource_size(&Resource::builder_empty().build(), encoding),
+                encoding,
+                circuit_breaker: self.circuit_breaker,
+                metrics,
+            });
+        }
+
+        // The compression is resolved here, the OTLP variables are never read
+        // by the OTLP exporter
+        Ok(LangfuseExporter {
+            inner: Backend::Otlp(OtlpExporter::new(
+                http_client,
+                endpoint,
+                self.protocol,
+                self.compression,
+            )),
+            max_batch_size: self.max_batch_size,
+            resource_size: batch::resource_size(&Resource::builder_empty().build(), encoding),
+            encoding,
+            circuit_breaker: self.circuit_breaker,
+            metrics,
+        })
+    }
+}
+
+/// Headers set by the exporter or its HTTP client, which must not be overridden.
+const RESERVED_HEADERS: [&str; 6] = [
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-type",
+    "host",
+    "transfer-encoding",
+];
+
+/// Converts the additional headers, naming the first invalid or reserved one.
+pub(crate) fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap> {
+    // Sorted, so that the same header is reported on every run
+    let mut headers: Vec<_> = headers.iter().collect();
+    headers.sort();
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            let header_name =
+                HeaderName::try_from(name.as_str()).map_err(|source| Error::InvalidHeaderName {
+                    name: name.clone(),
+                    source,
+                })?;
+            if RESERVED_HEADERS.contains(&header_name.as_str()) {
+                return Err(Error::ReservedHeader(name.clone()));
+            }
+            let header_value = HeaderValue::try_from(value.as_str()).map_err(|source| {
+                Error::InvalidHeaderValue {
+                    name: name.clone(),
+                    source,
+                }
+            })?;
+            Ok((header_name, header_value))
+        })
+        .collect()
+}
+
+/// Returns the first of the given environment variables that is set and not
+/// empty, with its name.
+fn first_env(names: &[&'static str]) -> Option<(&'static str, String)> {
+    names.iter().find_map(|&name| {
+        env::var(name)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| (name, value))
+    })
+}
+
+/// Reads the compression from `LANGFUSE_COMPRESSION`, falling back to
+/// `OTEL_EXPORTER_OTLP_TRACES_COMPRESSION` and `OTEL_EXPORTER_OTLP_COMPRESSION`.
+///
+/// Unset or empty variables, or `none`, mean no compression.
+fn compression_from_env() -> Result<Option<Compression>> {
+    let Some((name, value)) = first_env(&[
+        ENV_LANGFUSE_COMPRESSION,
+        ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION,
+        ENV_OTEL_EXPORTER_OTLP_COMPRESSION,
+    ]) else {
+        return Ok(None);
+    };
+
+    match value.trim().to_ascii_lowercase().as_str() {
+        "none" => Ok(None),
+        other => other
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::InvalidEnvironmentVariable { name, value }),
+    }
+}
+
+/// Reads the sample rate from `LANGFUSE_SAMPLE_RATE`.
+fn sample_rate_from_env() -> Result<Option<f64>> {
+    let Some((name, value)) = first_env(&[ENV_LANGFUSE_SAMPLE_RATE]) else {
+        return Ok(None);
+    };
+
+    match value.trim().parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(Some(rate)),
+        _ => Err(Error::InvalidEnvironmentVariable { name, value }),
+    }
+}
+
+/// Reads `LANGFUSE_TRACING_ENABLED`, `true` if unset or empty.
+fn tracing_enabled_from_env() -> Result<bool> {
+    let Some((name, value)) = first_env(&[ENV_LANGFUSE_TRACING_ENABLED]) else {
+        return Ok(true);
+    };
+
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(Error::InvalidEnvironmentVariable { name, value }),
+    }
+}
+
+/// Reads the timeout in milliseconds from `OTEL_EXPORTER_OTLP_TRACES_TIMEOUT`,
+/// falling back to `OTEL_EXPORTER_OTLP_TIMEOUT`.
+fn timeout_from_env() -> Result<Option<Duration>> {
+    let Some((name, value)) = first_env(&[
+        ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT,
+        ENV_OTEL_EXPORTER_OTLP_TIMEOUT,
+    ]) else {
+        return Ok(None);
+    };
+
+    value
+        .trim()
+        .parse()
+        .map(|millis| Some(Duration::from_millis(millis)))
+        .map_err(|_| Error::InvalidEnvironmentVariable { name, value })
+}
+
+/// Removes the Authorization header from `headers`, returning it as a secret.
+fn take_authorization(headers: &mut HashMap<String, String>) -> Option<SecretString> {
+    let name = headers
+        .keys()
+        .find(|name| name.eq_ignore_ascii_case(AUTHORIZATION.as_str()))?
+        .clone();
+    headers.remove(&name).map(SecretString::from)
+}
+
+/// Reads the headers from `OTEL_EXPORTER_OTLP_HEADERS`, overridden per name
+/// by `OTEL_EXPORTER_OTLP_TRACES_HEADERS`.
+fn headers_from_env() -> Result<HashMap<String, String>> {
+    let mut headers = HashMap::new();
+    for name in [
+        ENV_OTEL_EXPORTER_OTLP_HEADERS,
+        ENV_OTEL_EXPORTER_OTLP_TRACES_HEADERS,
+    ] {
+        let Ok(value) = env::var(name) else {
+            continue;
+        };
+        for (header, header_value) in
+            parse_headers(&value).ok_or_else(|| Error::InvalidEnvironmentVariable {
+                name,
+                value: value.clone(),
+            })?
+        {
+            headers.retain(|existing: &String, _| !existing.eq_ignore_ascii_case(&header));
+            headers.insert(header, header_value);
+        }
+    }
+    Ok(headers)
+}
+
+/// Parses `name=value` pairs separated by commas, as defined by the
+/// OpenTelemetry specification. Values are percent-decoded.
+///
+/// Returns `None` if a pair has no `=` or an empty name.
+fn parse_headers(value: &str) -> Option<Vec<(String, String)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            let name = name.trim();
+            if name.is_empty() {
+                return None;
+            }
+            let value = percent_encoding::percent_decode_str(value.trim()).decode_utf8_lossy();
+            Some((name.to_string(), value.into_owned()))
+        })
+        .collect()
+}
+
+impl Default for ExporterBuilder {
+    fn default() -> Self {
         Self::new()
     }
 }
 
+impl ExporterBuilder {
+    /// Returns the masked public key of the Basic Authorization header, if any.
+    fn masked_public_key(&self) -> Option<String> {
+        let authorization = self.auth_header.as_ref()?.expose_secret();
+        auth::decode_basic_auth(authorization)
+            .map(|(public_key, _)| auth::mask_public_key(&public_key))
+    }
+
+    /// Returns the names of the additional headers, sorted.
+    fn header_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.additional_headers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Prints the effective configuration without secrets: header values are
+/// omitted and the public key is masked.
+impl fmt::Debug for ExporterBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExporterBuilder")
+            .field("endpoint", &self.endpoint)
+            .field("public_key", &self.masked_public_key())
+            .field("credential_provider", &self.credential_provider.is_some())
+            .field("headers", &self.header_names())
+            .field("timeout", &self.timeout)
+            .field("compression", &self.compression)
+            .field("protocol", &self.protocol)
+            .field("transport", &self.transport)
+            .field("retry_policy", &self.retry_policy)
+            .field("spool", &self.spool)
+            .field("max_batch_size", &self.max_batch_size)
+            .field("allowed_regions", &self.allowed_regions)
+            .field("sample_rate", &self.sample_rate)
+            .field("tracing_enabled", &self.tracing_enabled)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Prints the endpoint, the masked public key and the header names, e.g. for
+/// logging the configuration at startup.
+impl fmt::Display for ExporterBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let public_key = match self.masked_public_key() {
+            Some(public_key) => public_key,
+            None if self.credential_provider.is_some() => "from credential provider".into(),
+            None => "none".into(),
+        };
+        write!(
+            f,
+            "Langfuse exporter (endpoint: {}, public key: {}, headers: [{}])",
+            self.endpoint.as_deref().unwrap_or("none"),
+            public_key,
+            self.header_names().join(", ")
+        )
+    }
+}
+
 /// Creates a Langfuse OTLP exporter using explicit configuration.
 ///
 /// # Arguments
@@ -262,7 +1271,7 @@ impl Default for ExporterBuilder {
 ///
 /// # Returns
 ///
-/// Returns a Result containing the configured SpanExporter if successful.
+/// Returns a Result containing the configured LangfuseExporter if successful.
 ///
 /// # Example
 ///
@@ -279,7 +1288,7 @@ impl Default for ExporterBuilder {
 /// # Ok(())
 /// # }
 /// ```
-pub fn exporter(host: &str, public_key: &str, secret_key: &str) -> Result<SpanExporter> {
+pub fn exporter(host: &str, public_key: &str, secret_key: &str) -> Result<LangfuseExporter> {
     ExporterBuilder::new()
         .with_host(host)
         .with_basic_auth(public_key, secret_key)
@@ -289,8 +1298,8 @@ pub fn exporter(host: &str, public_key: &str, secret_key: &str) -> Result<SpanEx
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::{ENV_LANGFUSE_ALLOWED_REGIONS, ENV_LANGFUSE_REGION};
     use serial_test::serial;
-    use std::env;
 
     #[test]
     #[serial]
@@ -321,6 +1330,967 @@ mod tests {
         env::remove_var("LANGFUSE_HOST");
     }
 
+    #[tokio::test]
+    async fn test_exporter_retries_transient_failures() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(503), MockResponse::status(200)]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_retry_policy(RetryPolicy::new().with_initial_backoff(Duration::from_millis(1)))
+            .build()
+            .unwrap();
+
+        let result = exporter.export(Vec::new()).await;
+
+        assert!(result.is_ok());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].path, "/api/public/otel/v1/traces");
+        assert_eq!(
+            requests[1].header("authorization"),
+            Some(auth::build_auth_header("pk-test", "sk-test").as_str())
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_exporter_from_env_reads_compression() {
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::set_var("LANGFUSE_SECRET_KEY", "sk-test");
+
+        env::set_var(ENV_LANGFUSE_COMPRESSION, "GZIP");
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert_eq!(builder.compression, Some(Compression::Gzip));
+
+        env::set_var(ENV_LANGFUSE_COMPRESSION, "none");
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert_eq!(builder.compression, None);
+
+        env::set_var(ENV_LANGFUSE_COMPRESSION, "brotli");
+        let result = ExporterBuilder::from_env();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_LANGFUSE_COMPRESSION,
+                ..
+            })
+        ));
+
+        env::remove_var(ENV_LANGFUSE_COMPRESSION);
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_compression_none_is_sent_uncompressed() {
+        use crate::test_util::{span_data, MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200); 2]);
+        env::set_var("LANGFUSE_HOST", server.url());
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::set_var("LANGFUSE_SECRET_KEY", "sk-test");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_COMPRESSION, "gzip");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION, "none");
+
+        // `none` in the OTEL variables is accepted
+        let exporter = ExporterBuilder::from_env().unwrap().build().unwrap();
+        exporter
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        // Neither are the OTEL variables read when building without them
+        env::remove_var(ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .build()
+            .unwrap();
+        exporter
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        for request in server.requests() {
+            assert_eq!(request.header("content-encoding"), None);
+        }
+
+        for name in [
+            ENV_OTEL_EXPORTER_OTLP_COMPRESSION,
+            "LANGFUSE_HOST",
+            "LANGFUSE_PUBLIC_KEY",
+            "LANGFUSE_SECRET_KEY",
+        ] {
+            env::remove_var(name);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_exporter_from_env_reads_otel_variables() {
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::set_var("LANGFUSE_SECRET_KEY", "sk-test");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_HEADERS, "x-team=core,x-env=dev");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_TRACES_HEADERS, "X-Env=prod%2Feu");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_TIMEOUT, "10000");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT, "2500");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_COMPRESSION, "zstd");
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION, "gzip");
+
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert_eq!(
+            builder.additional_headers,
+            HashMap::from([
+                ("x-team".to_string(), "core".to_string()),
+                ("X-Env".to_string(), "prod/eu".to_string()),
+            ])
+        );
+        assert_eq!(builder.timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(builder.compression, Some(Compression::Gzip));
+
+        // LANGFUSE_* variables and explicit calls take precedence
+        env::set_var(ENV_LANGFUSE_COMPRESSION, "none");
+        let builder = ExporterBuilder::from_env()
+            .unwrap()
+            .with_timeout(Duration::from_secs(1))
+            .with_header("x-team", "platform");
+        assert_eq!(builder.compression, None);
+        assert_eq!(builder.timeout, Some(Duration::from_secs(1)));
+        assert_eq!(builder.additional_headers["x-team"], "platform");
+
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT, "soon");
+        let result = ExporterBuilder::from_env();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT,
+                ..
+            })
+        ));
+
+        env::remove_var(ENV_OTEL_EXPORTER_OTLP_TRACES_TIMEOUT);
+        env::set_var(ENV_OTEL_EXPORTER_OTLP_HEADERS, "no-value");
+        let result = ExporterBuilder::from_env();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_OTEL_EXPORTER_OTLP_HEADERS,
+                ..
+            })
+        ));
+
+        for name in [
+            ENV_LANGFUSE_COMPRESSION,
+            ENV_OTEL_EXPORTER_OTLP_HEADERS,
+            ENV_OTEL_EXPORTER_OTLP_TRACES_HEADERS,
+            ENV_OTEL_EXPORTER_OTLP_TIMEOUT,
+            ENV_OTEL_EXPORTER_OTLP_COMPRESSION,
+            ENV_OTEL_EXPORTER_OTLP_TRACES_COMPRESSION,
+            "LANGFUSE_PUBLIC_KEY",
+            "LANGFUSE_SECRET_KEY",
+        ] {
+            env::remove_var(name);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_exporter_from_env_accepts_otel_authorization() {
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+        env::set_var(
+            ENV_OTEL_EXPORTER_OTLP_HEADERS,
+            "Authorization=Basic%20cGs6c2s=",
+        );
+
+        // The header is kept as a secret, out of the additional headers
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert_eq!(
+            builder
+                .auth_header
+                .as_ref()
+                .map(SecretString::expose_secret),
+            Some("Basic cGs6c2s=")
+        );
+        assert!(builder.additional_headers.is_empty());
+        assert!(builder.build().is_ok());
+
+        env::remove_var(ENV_OTEL_EXPORTER_OTLP_HEADERS);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_langfuse_credentials_override_otel_authorization() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        env::set_var("LANGFUSE_HOST", server.url());
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::set_var("LANGFUSE_SECRET_KEY", "sk-test");
+        env::set_var(
+            ENV_OTEL_EXPORTER_OTLP_HEADERS,
+            "authorization=Bearer%20other,x-team=core",
+        );
+
+        let exporter = ExporterBuilder::from_env().unwrap().build().unwrap();
+        exporter.export(Vec::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some(auth::build_auth_header("pk-test", "sk-test").as_str())
+        );
+        assert_eq!(requests[0].header("x-team"), Some("core"));
+
+        env::remove_var("LANGFUSE_HOST");
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+        env::remove_var(ENV_OTEL_EXPORTER_OTLP_HEADERS);
+    }
+
+    #[tokio::test]
+    async fn test_exporter_from_config() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let config = LangfuseConfig {
+            host: Some(server.url()),
+            public_key: Some("pk-test".into()),
+            secret_key: Some("sk-test".into()),
+            headers: HashMap::from([("x-team".into(), "core".into())]),
+            ..Default::default()
+        };
+
+        let exporter = ExporterBuilder::from_config(&config)
+            .unwrap()
+            .build()
+            .unwrap();
+        exporter.export(Vec::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/public/otel/v1/traces");
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some(auth::build_auth_header("pk-test", "sk-test").as_str())
+        );
+        assert_eq!(requests[0].header("x-team"), Some("core"));
+    }
+
+    #[tokio::test]
+    async fn test_exporter_uses_rotated_credentials() {
+        use crate::credentials::{Credentials, FnCredentialProvider};
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let server = MockServer::start(vec![MockResponse::status(200), MockResponse::status(200)]);
+        let rotations = AtomicUsize::new(0);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_credential_provider(FnCredentialProvider::new(move || {
+                let secret_key = format!("sk-{}", rotations.fetch_add(1, Ordering::SeqCst));
+                async move { Ok(Credentials::new("pk-test", secret_key)) }
+            }))
+            .build()
+            .unwrap();
+
+        exporter.export(Vec::new()).await.unwrap();
+        exporter.export(Vec::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some(auth::build_auth_header("pk-test", "sk-0").as_str())
+        );
+        assert_eq!(
+            requests[1].header("authorization"),
+            Some(auth::build_auth_header("pk-test", "sk-1").as_str())
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_exporter_from_env_reads_key_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret_path = dir.path().join("secret-key");
+        std::fs::write(&secret_path, "sk-from-file\n").unwrap();
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+        env::set_var(ENV_LANGFUSE_SECRET_KEY_FILE, &secret_path);
+
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert!(builder.auth_header.is_none());
+        assert!(builder.credential_provider.is_some());
+
+        env::set_var(ENV_LANGFUSE_SECRET_KEY_FILE, dir.path().join("missing"));
+        assert!(matches!(
+            ExporterBuilder::from_env(),
+            Err(Error::Credentials { .. })
+        ));
+
+        env::remove_var(ENV_LANGFUSE_SECRET_KEY_FILE);
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+    }
+
+    #[test]
+    fn test_invalid_endpoints_fail_to_build() {
+        let result = ExporterBuilder::new()
+            .with_host("htps://cloud.langfuse.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .build();
+        assert!(
+            matches!(result, Err(Error::InvalidEndpoint { url, .. }) if url == "htps://cloud.langfuse.com")
+        );
+
+        let result = ExporterBuilder::new()
+            .with_endpoint("https://cloud.langfuse.com/api/public/otel/v1/traces?debug=1")
+            .with_basic_auth("pk-test", "sk-test")
+            .build();
+        assert!(matches!(result, Err(Error::InvalidEndpoint { .. })));
+    }
+
+    #[test]
+    fn test_build_validates_headers() {
+        let build = |name: &str, value: &str| {
+            ExporterBuilder::new()
+                .with_host("https://cloud.langfuse.com")
+                .with_basic_auth("pk-test", "sk-test")
+                .with_header("x-team", "core")
+                .with_header(name, value)
+                .build()
+        };
+
+        assert!(build("x-env", "prod").is_ok());
+        assert!(matches!(
+            build("x env", "prod"),
+            Err(Error::InvalidHeaderName { name, .. }) if name == "x env"
+        ));
+        assert!(matches!(
+            build("x-env", "prod\r\nx-injected: 1"),
+            Err(Error::InvalidHeaderValue { name, .. }) if name == "x-env"
+        ));
+        assert!(matches!(
+            build("Content-Type", "application/json"),
+            Err(Error::ReservedHeader(name)) if name == "Content-Type"
+        ));
+    }
+
+    #[test]
+    fn test_region_allowlist() {
+        let us = || {
+            ExporterBuilder::new()
+                .with_region(LangfuseRegion::Us)
+                .with_basic_auth("pk-test", "sk-test")
+        };
+        assert_eq!(
+            us().endpoint.as_deref(),
+            Some("https://us.cloud.langfuse.com/api/public/otel/v1/traces")
+        );
+
+        assert!(us()
+            .with_allowed_regions([LangfuseRegion::Us, LangfuseRegion::Hipaa])
+            .build()
+            .is_ok());
+        assert!(matches!(
+            us().with_allowed_regions([LangfuseRegion::Eu]).build(),
+            Err(Error::RegionNotAllowed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_checks_region_allowlist() {
+        let result = ExporterBuilder::new()
+            .with_region(LangfuseRegion::Us)
+            .with_basic_auth("pk-test", "sk-test")
+            .with_allowed_regions([LangfuseRegion::Eu])
+            .verify()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(VerifyError::Configuration(Error::RegionNotAllowed { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_exporter_from_env_with_tracing_disabled() {
+        use crate::test_util::span_data;
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+        env::set_var(ENV_LANGFUSE_TRACING_ENABLED, "False");
+
+        // No keys needed, and every span is dropped
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert!(!builder.tracing_enabled());
+        assert!(matches!(builder.sampler(), Ok(Sampler::AlwaysOff)));
+        let exporter = builder.build().unwrap();
+        assert!(exporter
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .is_ok());
+
+        // An invalid sample rate does not matter either
+        env::set_var(ENV_LANGFUSE_SAMPLE_RATE, "1.5");
+        assert!(ExporterBuilder::from_env().is_ok());
+        env::remove_var(ENV_LANGFUSE_SAMPLE_RATE);
+
+        env::set_var(ENV_LANGFUSE_TRACING_ENABLED, "maybe");
+        assert!(matches!(
+            ExporterBuilder::from_env(),
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_LANGFUSE_TRACING_ENABLED,
+                ..
+            })
+        ));
+
+        env::remove_var(ENV_LANGFUSE_TRACING_ENABLED);
+    }
+
+    #[test]
+    #[serial]
+    fn test_exporter_from_env_reads_sample_rate() {
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::set_var("LANGFUSE_SECRET_KEY", "sk-test");
+
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert!(matches!(builder.sampler(), Ok(Sampler::AlwaysOn)));
+
+        env::set_var(ENV_LANGFUSE_SAMPLE_RATE, "0.25");
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert!(matches!(
+            builder.sampler(),
+            Ok(Sampler::TraceIdRatioBased(rate)) if rate == 0.25
+        ));
+
+        env::set_var(ENV_LANGFUSE_SAMPLE_RATE, "1.5");
+        assert!(matches!(
+            ExporterBuilder::from_env(),
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_LANGFUSE_SAMPLE_RATE,
+                ..
+            })
+        ));
+
+        env::remove_var(ENV_LANGFUSE_SAMPLE_RATE);
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+
+        let builder = ExporterBuilder::new()
+            .with_basic_auth("pk-test", "sk-test")
+            .with_sample_rate(-0.1);
+        assert!(matches!(
+            builder.sampler(),
+            Err(Error::InvalidConfiguration {
+                field: "sample_rate",
+                ..
+            })
+        ));
+        assert!(matches!(
+            builder.build(),
+            Err(Error::InvalidConfiguration {
+                field: "sample_rate",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_exporter_from_env_reads_region() {
+        env::set_var("LANGFUSE_PUBLIC_KEY", "pk-test");
+        env::set_var("LANGFUSE_SECRET_KEY", "sk-test");
+        env::set_var(ENV_LANGFUSE_REGION, "hipaa");
+
+        let builder = ExporterBuilder::from_env().unwrap();
+        assert_eq!(
+            builder.endpoint.as_deref(),
+            Some("https://hipaa.cloud.langfuse.com/api/public/otel/v1/traces")
+        );
+
+        env::set_var(ENV_LANGFUSE_ALLOWED_REGIONS, "eu");
+        assert!(matches!(
+            ExporterBuilder::from_env().and_then(|b| b.build()),
+            Err(Error::RegionNotAllowed { .. })
+        ));
+        env::remove_var(ENV_LANGFUSE_ALLOWED_REGIONS);
+
+        // A host in another region than LANGFUSE_REGION is a mistake
+        env::set_var("LANGFUSE_HOST", "https://cloud.langfuse.com");
+        assert!(matches!(
+            ExporterBuilder::from_env(),
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_LANGFUSE_REGION,
+                ..
+            })
+        ));
+
+        env::remove_var("LANGFUSE_HOST");
+        env::remove_var(ENV_LANGFUSE_REGION);
+        env::remove_var("LANGFUSE_PUBLIC_KEY");
+        env::remove_var("LANGFUSE_SECRET_KEY");
+    }
+
+    #[test]
+    fn test_builder_output_hides_secrets() {
+        let builder = ExporterBuilder::new()
+            .with_host("https://cloud.langfuse.com")
+            .with_basic_auth("pk-lf-1234567890abcdef", "sk-lf-secret")
+            .with_header("x-team", "team-secret");
+        let encoded = auth::build_auth_header("pk-lf-1234567890abcdef", "sk-lf-secret");
+
+        let debug = format!("{:?}", builder);
+        let display = builder.to_string();
+
+        assert_eq!(
+            display,
+            "Langfuse exporter (endpoint: https://cloud.langfuse.com/api/public/otel/v1/traces, \
+             public key: pk-lf-1234...cdef, headers: [x-team])"
+        );
+        assert!(debug.contains("pk-lf-1234...cdef"));
+        assert!(debug.contains("x-team"));
+        for output in [&debug, &display] {
+            assert!(!output.contains("sk-lf-secret"));
+            assert!(!output.contains("team-secret"));
+            assert!(!output.contains(&encoded[6..]));
+        }
+    }
+
+    #[cfg(feature = "gzip-http")]
+    #[tokio::test]
+    async fn test_exporter_sends_gzip_compressed_requests() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_compression(Compression::Gzip)
+            .build()
+            .unwrap();
+
+        exporter.export(Vec::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("content-encoding"), Some("gzip"));
+        // gzip magic bytes
+        assert_eq!(&requests[0].body[..2], &[0x1f, 0x8b]);
+    }
+
+    #[cfg(not(feature = "zstd-http"))]
+    #[test]
+    fn test_compression_without_feature_fails_to_build() {
+        let result = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .with_compression(Compression::Zstd)
+            .build();
+
+        assert!(matches!(result, Err(Error::FeatureNotEnabled("zstd-http"))));
+    }
+
+    #[cfg(feature = "http-json")]
+    #[tokio::test]
+    async fn test_exporter_sends_json_requests() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_protocol(Protocol::HttpJson)
+            .build()
+            .unwrap();
+
+        exporter.export(Vec::new()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("resourceSpans").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_exporter_defaults_to_protobuf() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .build()
+            .unwrap();
+
+        exporter.export(Vec::new()).await.unwrap();
+
+        assert_eq!(
+            server.requests()[0].header("content-type"),
+            Some("application/x-protobuf")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exporter_splits_batches_above_max_size() {
+        use crate::test_util::{span_data, MockResponse, MockServer};
+        use opentelemetry::KeyValue;
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_max_batch_size(4000)
+            .build()
+            .unwrap();
+
+        let mut batch: Vec<_> = (1..=5)
+            .map(|id| span_data(1, id, 0, vec![KeyValue::new("input", "x".repeat(1500))]))
+            .collect();
+        batch.push(span_data(
+            1,
+            6,
+            0,
+            vec![KeyValue::new("input", "y".repeat(20_000))],
+        ));
+        exporter.export(batch).await.unwrap();
+
+        let requests = server.requests();
+        assert!(requests.len() > 2);
+        for request in &requests {
+            assert!(request.body.len() <= 4000);
+        }
+        let last = &requests.last().unwrap().body;
+        let marker = batch::TRUNCATION_MARKER.as_bytes();
+        assert!(last.windows(marker.len()).any(|w| w == marker));
+    }
+
+    #[tokio::test]
+    async fn test_exporter_records_metrics() {
+        use crate::test_util::{span_data, MockResponse, MockServer};
+        use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+        use opentelemetry_sdk::metrics::{
+            InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+        };
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let metrics = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(metrics.clone()).build())
+            .build();
+        let server = MockServer::start(vec![MockResponse::status(503), MockResponse::status(200)]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_retry_policy(RetryPolicy::new().with_initial_backoff(Duration::from_millis(1)))
+            .with_meter_provider(&provider)
+            .build()
+            .unwrap();
+
+        let batch = (1..=3).map(|id| span_data(1, id, 0, vec![])).collect();
+        exporter.export(batch).await.unwrap();
+        provider.force_flush().unwrap();
+
+        let finished = metrics.get_finished_metrics().unwrap();
+        let scope = finished
+            .last()
+            .unwrap()
+            .scope_metrics()
+            .find(|scope| scope.scope().name() == "opentelemetry-langfuse")
+            .unwrap();
+        let sum = |name: &str| {
+            scope
+                .metrics()
+                .find(|metric| metric.name() == name)
+                .map(|metric| match metric.data() {
+                    AggregatedMetrics::U64(MetricData::Sum(sum)) => {
+                        sum.data_points().map(|point| point.value()).sum::<u64>()
+                    }
+                    _ => panic!("unexpected data for {}", name),
+                })
+                .unwrap_or(0)
+        };
+
+        let bytes: usize = server.requests().iter().map(|r| r.body.len()).sum();
+        assert_eq!(sum("langfuse.exporter.spans.exported"), 3);
+        assert_eq!(sum("langfuse.exporter.spans.failed"), 0);
+        assert_eq!(sum("langfuse.exporter.batches.retried"), 1);
+        assert_eq!(sum("langfuse.exporter.bytes.sent"), bytes as u64);
+
+        let duration = scope
+            .metrics()
+            .find(|metric| metric.name() == "langfuse.exporter.export.duration")
+            .unwrap();
+        let AggregatedMetrics::F64(MetricData::Histogram(histogram)) = duration.data() else {
+            panic!("unexpected data for export duration");
+        };
+        let point = histogram.data_points().next().unwrap();
+        assert_eq!(point.count(), 1);
+        assert_eq!(
+            point.attributes().next().unwrap(),
+            &opentelemetry::KeyValue::new("http.status_class", "2xx")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replayed_spans_count_as_exported() {
+        use crate::test_util::{span_data, MockResponse, MockServer};
+        use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+        use opentelemetry_sdk::metrics::{
+            InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+        };
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let metrics = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(metrics.clone()).build())
+            .build();
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start(vec![
+            MockResponse::status(503),
+            MockResponse::status(200),
+            MockResponse::status(200),
+        ]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_spool(SpoolConfig::new(dir.path()))
+            .with_meter_provider(&provider)
+            .build()
+            .unwrap();
+
+        let batch = (1..=2).map(|id| span_data(1, id, 0, vec![])).collect();
+        assert!(exporter.export(batch).await.is_err());
+        exporter
+            .export(vec![span_data(2, 1, 0, vec![])])
+            .await
+            .unwrap();
+        provider.force_flush().unwrap();
+
+        let finished = metrics.get_finished_metrics().unwrap();
+        let scope = finished
+            .last()
+            .unwrap()
+            .scope_metrics()
+            .find(|scope| scope.scope().name() == "opentelemetry-langfuse")
+            .unwrap();
+        let points = |name: &str| {
+            let metric = scope
+                .metrics()
+                .find(|metric| metric.name() == name)
+                .unwrap();
+            let AggregatedMetrics::U64(MetricData::Sum(sum)) = metric.data() else {
+                panic!("unexpected data for {}", name);
+            };
+            let mut points: Vec<_> = sum
+                .data_points()
+                .map(|point| {
+                    let class = point.attributes().next().unwrap().value.to_string();
+                    (class, point.value())
+                })
+                .collect();
+            points.sort();
+            points
+        };
+
+        assert_eq!(
+            points("langfuse.exporter.spans.failed"),
+            [("5xx".to_string(), 2)]
+        );
+        assert_eq!(
+            points("langfuse.exporter.spans.exported"),
+            [("2xx".to_string(), 3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exporter_with_ingestion_transport() {
+        use crate::test_util::{span_data, MockResponse, MockServer};
+        use opentelemetry_sdk::trace::SpanExporter as _;
+
+        let server = MockServer::start(vec![
+            MockResponse::status(207).with_body(r#"{"successes":[],"errors":[]}"#)
+        ]);
+        let exporter = ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+            .with_transport(Transport::Ingestion)
+            .build()
+            .unwrap();
+
+        exporter
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/api/public/ingestion");
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some(auth::build_auth_header("pk-test", "sk-test").as_str())
+        );
+    }
+
+    #[test]
+    fn test_ingestion_transport_rejects_compression() {
+        let result = ExporterBuilder::new()
+            .with_host("https://cloud.langfuse.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .with_transport(Transport::Ingestion)
+            .with_compression(Compression::Gzip)
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidConfiguration {
+                field: "compression",
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "blocking-client")]
+    #[test]
+    fn test_blocking_exporter_works_without_runtime() {
+        use crate::test_util::{MockResponse, MockServer};
+        use opentelemetry::trace::{Tracer, TracerProvider as _};
+        use opentelemetry_sdk::trace::SdkTracerProvider;
+
+        assert!(tokio::runtime::Handle::try_current().is_err());
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let exporter = |server: &MockServer| {
+            ExporterBuilder::new()
+                .with_host(&server.url())
+                .with_basic_auth("pk-test", "sk-test")
+                .with_blocking_http_client(reqwest::blocking::Client::new())
+                .build()
+                .unwrap()
+        };
+
+        // SimpleSpanProcessor exports when the span ends
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter(&server))
+            .build();
+        provider.tracer("test").in_span("simple", |_| {});
+        assert_eq!(server.requests().len(), 1);
+        provider.shutdown().unwrap();
+
+        // The thread-based BatchSpanProcessor exports on flush
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter(&server))
+            .build();
+        provider.tracer("test").in_span("batch", |_| {});
+        provider.force_flush().unwrap();
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(server.requests()[1].path, "/api/public/otel/v1/traces");
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_tls_settings_with_custom_client_fail_to_build() {
+        let result = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .with_http_client(reqwest::Client::new())
+            .with_tls_min_version(TlsVersion::Tls1_2)
+            .build();
+
+        assert!(matches!(result, Err(Error::Tls { .. })));
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    #[test]
+    fn test_exporter_with_tls_settings() {
+        let result = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .with_tls_backend(TlsBackend::Rustls)
+            .with_tls_min_version(TlsVersion::Tls1_3)
+            .build();
+        assert!(result.is_ok());
+
+        let result = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .with_ca_certificate_pem("not a certificate")
+            .build();
+        assert!(matches!(result, Err(Error::Tls { .. })));
+    }
+
+    #[cfg(not(feature = "http-json"))]
+    #[test]
+    fn test_json_protocol_without_feature_fails_to_build() {
+        let result = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_basic_auth("pk-test", "sk-test")
+            .with_protocol(Protocol::HttpJson)
+            .build();
+
+        assert!(matches!(result, Err(Error::FeatureNotEnabled("http-json"))));
+    }
+
+    #[test]
+    fn test_authorization_header_is_kept_secret() {
+        let builder = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_headers([("authorization", "Bearer secret-token"), ("x-team", "core")]);
+
+        assert_eq!(
+            builder
+                .auth_header
+                .as_ref()
+                .map(SecretString::expose_secret),
+            Some("Bearer secret-token")
+        );
+        assert_eq!(builder.header_names(), ["x-team"]);
+        assert!(!format!("{:?}", builder).contains("secret-token"));
+
+        // Later credentials replace it
+        let builder = builder.with_basic_auth("pk-test", "sk-test");
+        assert_eq!(
+            builder
+                .auth_header
+                .as_ref()
+                .map(SecretString::expose_secret),
+            Some(auth::build_auth_header("pk-test", "sk-test").as_str())
+        );
+    }
+
+    #[test]
+    fn test_authorization_header_replaces_credential_provider() {
+        use crate::credentials::{Credentials, FnCredentialProvider};
+
+        let builder = ExporterBuilder::new()
+            .with_endpoint("https://test.com")
+            .with_credential_provider(FnCredentialProvider::new(|| async {
+                Ok(Credentials::new("pk-test", "sk-test"))
+            }))
+            .with_header("Authorization", "Bearer secret-token");
+
+        assert!(builder.credential_provider.is_none());
+        assert_eq!(
+            builder
+                .auth_header
+                .as_ref()
+                .map(SecretString::expose_secret),
+            Some("Bearer secret-token")
+        );
+    }
+
     #[test]
     fn test_case_insensitive_authorization_header() {
         // Test that authorization header is handled case-insensitively
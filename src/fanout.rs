@@ -0,0 +1,457 @@
+//! Fan-out exporter that sends spans to Langfuse and additional OTLP backends.
+//!
+//! A [`FanoutExporter`] sends every batch to Langfuse and tees it to any number
+//! of secondary OTLP destinations, such as a Tempo or Jaeger collector. Each
+//! secondary destination is exported to on a worker thread of its own, with
+//! its own timeout and in-flight limit, so a slow or failing destination never
+//! stalls or fails the Langfuse export. The worker runs the exports on a Tokio
+//! runtime of its own, so the application needs none.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{ExporterBuilder, FanoutExporter};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = FanoutExporter::builder(ExporterBuilder::from_env()?)
+//!     .with_otlp_endpoint("tempo", "http://tempo:4318/v1/traces")?
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{ExporterBuilder, LangfuseExporter, Result};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter as _};
+use opentelemetry_sdk::Resource;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinSet;
+
+/// Default timeout for an export to a secondary destination.
+const DEFAULT_DESTINATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default number of concurrent exports per secondary destination.
+const DEFAULT_MAX_IN_FLIGHT: usize = 2;
+
+/// Work for the worker thread of a secondary destination.
+enum Message {
+    Export(Vec<SpanData>),
+    SetResource(Resource),
+    /// Finish or abort the running exports by the deadline, shut the exporter
+    /// down and report back.
+    Shutdown {
+        deadline: Instant,
+        done: mpsc::Sender<()>,
+    },
+}
+
+/// A secondary destination of a [`FanoutExporter`].
+#[derive(Debug)]
+struct Destination {
+    name: String,
+    sender: UnboundedSender<Message>,
+    in_flight: Arc<AtomicUsize>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Destination {
+    /// Starts the worker thread exporting to `exporter`.
+    fn start(name: String, exporter: SpanExporter, timeout: Duration) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (sender, receiver) = unbounded_channel();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+
+        let worker = Worker {
+            exporter: Arc::new(exporter),
+            in_flight: Arc::clone(&in_flight),
+            timeout,
+        };
+        thread::Builder::new()
+            .name(format!("langfuse-fanout-{}", name))
+            .spawn(move || runtime.block_on(worker.run(receiver)))?;
+
+        Ok(Self {
+            name,
+            sender,
+            in_flight,
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Hands the batch to the worker thread.
+    ///
+    /// The batch is dropped if the destination already has `max_in_flight`
+    /// exports queued or running.
+    fn dispatch(&self, batch: Vec<SpanData>, max_in_flight: usize) {
+        if self.in_flight.fetch_add(1, Ordering::AcqRel) >= max_in_flight
+            || self.sender.send(Message::Export(batch)).is_err()
+        {
+            self.in_flight.fetch_sub(1, Ordering::AcqRel);
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Worker thread of a secondary destination.
+struct Worker {
+    exporter: Arc<SpanExporter>,
+    in_flight: Arc<AtomicUsize>,
+    timeout: Duration,
+}
+
+impl Worker {
+    async fn run(mut self, mut receiver: UnboundedReceiver<Message>) {
+        let mut exports = JoinSet::new();
+        while let Some(message) = receiver.recv().await {
+            // Forget the finished exports
+            while exports.try_join_next().is_some() {}
+
+            match message {
+                Message::Export(batch) => {
+                    let exporter = Arc::clone(&self.exporter);
+                    let in_flight = Arc::clone(&self.in_flight);
+                    let timeout = self.timeout;
+                    exports.spawn(async move {
+                        let _ = tokio::time::timeout(timeout, exporter.export(batch)).await;
+                        in_flight.fetch_sub(1, Ordering::AcqRel);
+                    });
+                }
+                Message::SetResource(resource) => {
+                    // The resource is set before the first export, wait in case it is not
+                    while exports.join_next().await.is_some() {}
+                    if let Some(exporter) = Arc::get_mut(&mut self.exporter) {
+                        exporter.set_resource(&resource);
+                    }
+                }
+                Message::Shutdown { deadline, done } => {
+                    let deadline = tokio::time::Instant::from_std(deadline);
+                    let _ = tokio::time::timeout_at(deadline, async {
+                        while exports.join_next().await.is_some() {}
+                    })
+                    .await;
+                    exports.shutdown().await;
+                    self.in_flight.store(0, Ordering::Release);
+
+                    if let Some(exporter) = Arc::get_mut(&mut self.exporter) {
+                        let remaining =
+                            deadline.saturating_duration_since(tokio::time::Instant::now());
+                        let _ = exporter.shutdown_with_timeout(remaining);
+                    }
+                    let _ = done.send(());
+                    return;
+                }
+            }
+        }
+
+        // The exporter was dropped without shutdown, let the exports finish
+        while exports.join_next().await.is_some() {}
+    }
+}
+
+/// Exporter that sends every batch to Langfuse and to secondary OTLP destinations.
+///
+/// Only the result of the Langfuse export is reported to the span processor.
+/// Failures and timeouts of secondary destinations are isolated from it.
+#[derive(Debug)]
+pub struct FanoutExporter {
+    langfuse: LangfuseExporter,
+    destinations: Vec<Destination>,
+    max_in_flight: usize,
+}
+
+impl FanoutExporter {
+    /// Creates a builder that sends spans to the Langfuse exporter built from `langfuse`.
+    pub fn builder(langfuse: ExporterBuilder) -> FanoutExporterBuilder {
+        FanoutExporterBuilder::new(langfuse)
+    }
+
+    /// Returns the number of batches dropped for a secondary destination
+    /// because it had too many exports in flight.
+    ///
+    /// Returns `None` if no destination with that name exists.
+    pub fn dropped_batches(&self, name: &str) -> Option<u64> {
+        self.destinations
+            .iter()
+            .find(|d| d.name == name)
+            .map(|d| d.dropped.load(Ordering::Relaxed))
+    }
+}
+
+impl opentelemetry_sdk::trace::SpanExporter for FanoutExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        for destination in &self.destinations {
+            destination.dispatch(batch.clone(), self.max_in_flight);
+        }
+
+        self.langfuse.export(batch).await
+    }
+
+    /// Shuts the secondary destinations and Langfuse down in parallel, all
+    /// within `timeout`.
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        let deadline = Instant::now() + timeout;
+        let pending: Vec<_> = self
+            .destinations
+            .iter()
+            .filter_map(|destination| {
+                let (done, receiver) = mpsc::channel();
+                destination
+                    .sender
+                    .send(Message::Shutdown { deadline, done })
+                    .ok()
+                    .map(|()| receiver)
+            })
+            .collect();
+
+        let result = self
+            .langfuse
+            .shutdown_with_timeout(deadline.saturating_duration_since(Instant::now()));
+        for receiver in pending {
+            let _ = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+        result
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        for destination in &self.destinations {
+            let _ = destination
+                .sender
+                .send(Message::SetResource(resource.clone()));
+        }
+
+        self.langfuse.set_resource(resource);
+    }
+}
+
+/// Builder for a [`FanoutExporter`].
+pub struct FanoutExporterBuilder {
+    langfuse: ExporterBuilder,
+    destinations: Vec<(String, SpanExporter)>,
+    max_in_flight: usize,
+    timeout: Duration,
+}
+
+impl FanoutExporterBuilder {
+    /// Creates a builder that sends spans to the Langfuse exporter built from `langfuse`.
+    pub fn new(langfuse: ExporterBuilder) -> Self {
+        Self {
+            langfuse,
+            destinations: Vec::new(),
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            timeout: DEFAULT_DESTINATION_TIMEOUT,
+        }
+    }
+
+    /// Adds a secondary destination using a preconfigured OTLP exporter.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name identifying the destination
+    /// * `exporter` - The OTLP exporter for the destination
+    pub fn with_destination(mut self, name: impl Into<String>, exporter: SpanExporter) -> Self {
+        self.destinations.push((name.into(), exporter));
+        self
+    }
+
+    /// Adds a secondary OTLP/HTTP destination.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name identifying the destination
+    /// * `endpoint` - The complete OTLP/HTTP traces endpoint URL
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the OTLP exporter cannot be built.
+    pub fn with_otlp_endpoint(
+        self,
+        name: impl Into<String>,
+        endpoint: impl Into<String>,
+    ) -> Result<Self> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_http_client(reqwest::Client::new())
+            .with_endpoint(endpoint)
+            .build()?;
+        Ok(self.with_destination(name, exporter))
+    }
+
+    /// Sets the maximum number of concurrent exports per secondary destination.
+    ///
+    /// Batches for a destination that is still busy with this many exports are
+    /// dropped for that destination only. Defaults to 2.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Sets the timeout for exports to secondary destinations.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn with_destination_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Builds the fan-out exporter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Langfuse exporter cannot be built, or if the
+    /// worker thread of a destination cannot be started.
+    pub fn build(self) -> Result<FanoutExporter> {
+        let langfuse = self.langfuse.build()?;
+        let destinations = self
+            .destinations
+            .into_iter()
+            .map(|(name, exporter)| Destination::start(name, exporter, self.timeout))
+            .collect::<Result<_>>()?;
+
+        Ok(FanoutExporter {
+            langfuse,
+            destinations,
+            max_in_flight: self.max_in_flight,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockResponse, MockServer};
+    use std::future::Future;
+    use std::net::TcpListener;
+
+    fn langfuse(server: &MockServer) -> ExporterBuilder {
+        ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+    }
+
+    async fn wait_for_requests(server: &MockServer, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.requests().len() < count && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sends_batches_to_all_destinations() {
+        let primary = MockServer::start(vec![MockResponse::status(200)]);
+        let secondary = MockServer::start(vec![MockResponse::status(200)]);
+
+        let exporter = FanoutExporter::builder(langfuse(&primary))
+            .with_otlp_endpoint("collector", format!("{}/v1/traces", secondary.url()))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        exporter.export(Vec::new()).await.unwrap();
+        wait_for_requests(&secondary, 1).await;
+
+        assert_eq!(primary.requests().len(), 1);
+        let requests = secondary.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1/traces");
+        // Langfuse credentials must not leak to the secondary destination
+        assert_eq!(requests[0].header("authorization"), None);
+    }
+
+    #[tokio::test]
+    async fn test_secondary_failure_does_not_fail_export() {
+        let primary = MockServer::start(vec![MockResponse::status(200)]);
+        let secondary = MockServer::start(vec![MockResponse::status(500)]);
+
+        let exporter = FanoutExporter::builder(langfuse(&primary))
+            .with_otlp_endpoint("collector", secondary.url())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(exporter.export(Vec::new()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_slow_secondary_does_not_stall_langfuse() {
+        let primary = MockServer::start(vec![MockResponse::status(200)]);
+        // Accepts connections but never answers
+        let stalled = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stalled_url = format!("http://{}", stalled.local_addr().unwrap());
+
+        let exporter = FanoutExporter::builder(langfuse(&primary))
+            .with_otlp_endpoint("stalled", stalled_url)
+            .unwrap()
+            .with_max_in_flight(1)
+            .with_destination_timeout(Duration::from_secs(30))
+            .build()
+            .unwrap();
+
+        let started = Instant::now();
+        exporter.export(Vec::new()).await.unwrap();
+        exporter.export(Vec::new()).await.unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(primary.requests().len(), 2);
+        assert_eq!(exporter.dropped_batches("stalled"), Some(1));
+        assert_eq!(exporter.dropped_batches("unknown"), None);
+    }
+
+    #[test]
+    fn test_exports_without_runtime() {
+        let secondary = MockServer::start(vec![MockResponse::status(200)]);
+        let exporter = FanoutExporter::builder(ExporterBuilder::new().with_tracing_enabled(false))
+            .with_otlp_endpoint("collector", secondary.url())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // Nothing to wait for with tracing disabled, so a single poll completes the export
+        let mut export = std::pin::pin!(exporter.export(Vec::new()));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        assert!(matches!(
+            export.as_mut().poll(&mut cx),
+            std::task::Poll::Ready(Ok(()))
+        ));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while secondary.requests().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(secondary.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_shutdown_respects_deadline() {
+        // Accepts connections but never answers
+        let stalled = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut exporter =
+            FanoutExporter::builder(ExporterBuilder::new().with_tracing_enabled(false))
+                .with_otlp_endpoint("first", format!("http://{}", stalled.local_addr().unwrap()))
+                .unwrap()
+                .with_otlp_endpoint(
+                    "second",
+                    format!("http://{}", stalled.local_addr().unwrap()),
+                )
+                .unwrap()
+                .with_destination_timeout(Duration::from_secs(30))
+                .build()
+                .unwrap();
+        for destination in &exporter.destinations {
+            destination.dispatch(Vec::new(), 1);
+        }
+
+        let started = Instant::now();
+        exporter
+            .shutdown_with_timeout(Duration::from_millis(200))
+            .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,1106 @@
+//! Offline exporter writing spans to rotating NDJSON files.
+//!
+//! A [`FileExporter`] writes every span, together with its resource and
+//! instrumentation scope, as one JSON object per line to files in a directory
+//! instead of sending it. Once the files have been moved to a machine that can
+//! reach Langfuse, [`upload`] reads them back and sends them through a regular
+//! [`ExporterBuilder`] configuration.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{file, ExporterBuilder, FileExporter};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // In the air-gapped environment
+//! let exporter = FileExporter::builder("/var/lib/my-service/spans")
+//!     .with_max_file_size(16 * 1024 * 1024)
+//!     .build()?;
+//!
+//! // Later, where Langfuse is reachable
+//! let summary = file::upload("/mnt/transfer/spans", ExporterBuilder::from_env()?).await?;
+//! println!("Uploaded {} spans from {} files", summary.spans, summary.files);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, ExporterBuilder, Result};
+use opentelemetry::trace::{
+    Event, Link, SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::{Array, InstrumentationScope, KeyValue, Value};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::{SpanData, SpanEvents, SpanExporter as _, SpanLinks};
+use opentelemetry_sdk::Resource;
+use serde::de::{self, Unexpected};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// File extension of span files.
+const FILE_EXTENSION: &str = "ndjson";
+
+/// Prefix of span file names.
+const FILE_PREFIX: &str = "spans-";
+
+/// Default size after which a new span file is started (64 MiB).
+const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Number of spans sent per export request by [`upload`].
+const UPLOAD_BATCH_SIZE: usize = 512;
+
+/// File currently being written to.
+#[derive(Debug)]
+struct ActiveFile {
+    file: File,
+    size: u64,
+}
+
+#[derive(Debug)]
+struct State {
+    active: Option<ActiveFile>,
+    next_sequence: u64,
+}
+
+/// Exporter that writes spans to rotating NDJSON files.
+///
+/// Files are named `spans-<sequence>.ndjson`, so sorting them by name yields
+/// the order in which they were written. A new file is started when the
+/// current one would grow past the configured size, and when the exporter is
+/// created again, e.g. after a restart. Only one exporter should write to a
+/// directory at a time.
+#[derive(Debug)]
+pub struct FileExporter {
+    directory: PathBuf,
+    max_file_size: u64,
+    resource: ResourceRecord,
+    state: Mutex<State>,
+}
+
+impl FileExporter {
+    /// Creates a builder writing span files to `directory`.
+    pub fn builder(directory: impl Into<PathBuf>) -> FileExporterBuilder {
+        FileExporterBuilder::new(directory)
+    }
+
+    /// Returns the directory the span files are written to.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn write(&self, lines: &[u8]) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let full = state.active.as_ref().is_some_and(|active| {
+            active.size > 0 && active.size + lines.len() as u64 > self.max_file_size
+        });
+        if full {
+            state.active = None;
+        }
+
+        let active = match &mut state.active {
+            Some(active) => active,
+            None => {
+                let path = file_path(&self.directory, state.next_sequence);
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                state.next_sequence += 1;
+                state.active.insert(ActiveFile { file, size: 0 })
+            }
+        };
+
+        active.file.write_all(lines)?;
+        active.file.flush()?;
+        active.size += lines.len() as u64;
+        Ok(())
+    }
+}
+
+impl opentelemetry_sdk::trace::SpanExporter for FileExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = Vec::new();
+        for span in &batch {
+            let record = SpanRecord::new(span, &self.resource);
+            serde_json::to_writer(&mut lines, &record)
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+            lines.push(b'\n');
+        }
+
+        self.write(&lines)
+            .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+    }
+
+    fn shutdown_with_timeout(&mut self, _timeout: Duration) -> OTelSdkResult {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(active) = state.active.take() {
+            active
+                .file
+                .sync_all()
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = ResourceRecord::new(resource);
+    }
+}
+
+/// Builder for a [`FileExporter`].
+#[derive(Debug, Clone)]
+pub struct FileExporterBuilder {
+    directory: PathBuf,
+    max_file_size: u64,
+}
+
+impl FileExporterBuilder {
+    /// Creates a builder writing span files to `directory`.
+    ///
+    /// The directory is created if it does not exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+
+    /// Sets the size after which a new span file is started.
+    ///
+    /// A single batch is never split across files, so a file may exceed this
+    /// size by up to one batch. Defaults to 64 MiB.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes.max(1);
+        self
+    }
+
+    /// Builds the file exporter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or read.
+    pub fn build(self) -> Result<FileExporter> {
+        fs::create_dir_all(&self.directory)?;
+        let next_sequence = span_files(&self.directory)?
+            .last()
+            .and_then(|path| file_sequence(path))
+            .map_or(0, |sequence| sequence + 1);
+
+        Ok(FileExporter {
+            directory: self.directory,
+            max_file_size: self.max_file_size,
+            resource: ResourceRecord::new(&Resource::builder_empty().build()),
+            state: Mutex::new(State {
+                active: None,
+                next_sequence,
+            }),
+        })
+    }
+}
+
+/// Summary of an [`upload`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UploadSummary {
+    /// Number of files uploaded.
+    pub files: usize,
+    /// Number of spans uploaded.
+    pub spans: usize,
+}
+
+/// Uploads the span files in `directory` to Langfuse.
+///
+/// Files written by a [`FileExporter`] are read in the order they were written
+/// and sent with the exporter built from `exporter`, keeping the resource of
+/// each span. The files are left in place; remove them once the upload
+/// succeeded. Uploading a file again is safe, as Langfuse deduplicates spans by
+/// their IDs.
+///
+/// Only upload files that are no longer written to.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or parsed, if the exporter cannot
+/// be built, or if an export fails. Files before the failing one have been
+/// uploaded completely, and the failing one up to the failing batch.
+///
+/// A truncated last line, as left by a crash during a write, is skipped.
+pub async fn upload(
+    directory: impl AsRef<Path>,
+    exporter: ExporterBuilder,
+) -> Result<UploadSummary> {
+    let mut exporter = exporter.build()?;
+    let mut summary = UploadSummary::default();
+
+    for path in span_files(directory.as_ref())? {
+        // Spans are read and exported in groups sharing the same resource
+        let mut file = SpanFile::open(&path)?;
+        while let Some((resource, batch)) = file.read_batch(UPLOAD_BATCH_SIZE)? {
+            let count = batch.len();
+            exporter.set_resource(&resource.to_resource());
+            exporter.export(batch).await?;
+            summary.spans += count;
+        }
+
+        summary.files += 1;
+    }
+
+    exporter.shutdown()?;
+    Ok(summary)
+}
+
+/// Reader of the spans of a span file, with their resources.
+struct SpanFile {
+    path: PathBuf,
+    reader: BufReader<File>,
+    /// Number of the last line read.
+    line: usize,
+    /// Span read ahead of the current batch.
+    next: Option<(ResourceRecord, SpanData)>,
+}
+
+impl SpanFile {
+    fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::new(File::open(path)?),
+            line: 0,
+            next: None,
+        })
+    }
+
+    /// Reads the next span, or `None` at the end of the file.
+    ///
+    /// A truncated last line, e.g. from a crash during a write, is ignored.
+    fn read_span(&mut self) -> Result<Option<(ResourceRecord, SpanData)>> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            let invalid = |message: String| Error::InvalidSpanFile {
+                path: self.path.clone(),
+                line: self.line,
+                message,
+            };
+            let record: SpanRecord = match serde_json::from_slice(&line) {
+                Ok(record) => record,
+                // Every complete line ends with a newline
+                Err(_) if !line.ends_with(b"\n") => return Ok(None),
+                Err(e) => return Err(invalid(e.to_string())),
+            };
+            let resource = record.resource.clone();
+            return Ok(Some((resource, record.into_span_data().map_err(invalid)?)));
+        }
+    }
+
+    /// Reads up to `max` spans sharing the same resource, or `None` at the
+    /// end of the file.
+    fn read_batch(&mut self, max: usize) -> Result<Option<(ResourceRecord, Vec<SpanData>)>> {
+        let first = match self.next.take() {
+            Some(next) => next,
+            None => match self.read_span()? {
+                Some(span) => span,
+                None => return Ok(None),
+            },
+        };
+        let (resource, span) = first;
+
+        let mut batch = vec![span];
+        while batch.len() < max {
+            match self.read_span()? {
+                Some((next, span)) if next == resource => batch.push(span),
+                next => {
+                    self.next = next;
+                    break;
+                }
+            }
+        }
+        Ok(Some((resource, batch)))
+    }
+}
+
+/// Returns the span files in `directory`, sorted by sequence.
+fn span_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| file_sequence(path).is_some())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn file_path(directory: &Path, sequence: u64) -> PathBuf {
+    directory.join(format!(
+        "{}{:020}.{}",
+        FILE_PREFIX, sequence, FILE_EXTENSION
+    ))
+}
+
+fn file_sequence(path: &Path) -> Option<u64> {
+    if path.extension()? != FILE_EXTENSION {
+        return None;
+    }
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(FILE_PREFIX)?
+        .parse()
+        .ok()
+}
+
+/// A span as written to a span file.
+#[derive(Debug, Serialize, Deserialize)]
+struct SpanRecord {
+    resource: ResourceRecord,
+    scope: ScopeRecord,
+    trace_id: String,
+    span_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    #[serde(default)]
+    parent_span_is_remote: bool,
+    trace_flags: u8,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    trace_state: String,
+    #[serde(default)]
+    is_remote: bool,
+    name: String,
+    kind: KindRecord,
+    start_time_unix_nano: u64,
+    end_time_unix_nano: u64,
+    attributes: Vec<AttributeRecord>,
+    #[serde(default)]
+    dropped_attributes_count: u32,
+    #[serde(default)]
+    events: Vec<EventRecord>,
+    #[serde(default)]
+    dropped_events_count: u32,
+    #[serde(default)]
+    links: Vec<LinkRecord>,
+    #[serde(default)]
+    dropped_links_count: u32,
+    status: StatusRecord,
+}
+
+impl SpanRecord {
+    fn new(span: &SpanData, resource: &ResourceRecord) -> Self {
+        let context = &span.span_context;
+        Self {
+            resource: resource.clone(),
+            scope: ScopeRecord::new(&span.instrumentation_scope),
+            trace_id: context.trace_id().to_string(),
+            span_id: context.span_id().to_string(),
+            parent_span_id: (span.parent_span_id != SpanId::INVALID)
+                .then(|| span.parent_span_id.to_string()),
+            parent_span_is_remote: span.parent_span_is_remote,
+            trace_flags: context.trace_flags().to_u8(),
+            trace_state: context.trace_state().header(),
+            is_remote: context.is_remote(),
+            name: span.name.to_string(),
+            kind: KindRecord::from(&span.span_kind),
+            start_time_unix_nano: to_nanos(span.start_time),
+            end_time_unix_nano: to_nanos(span.end_time),
+            attributes: attributes(&span.attributes),
+            dropped_attributes_count: span.dropped_attributes_count,
+            events: span.events.iter().map(EventRecord::new).collect(),
+            dropped_events_count: span.events.dropped_count,
+            links: span.links.iter().map(LinkRecord::new).collect(),
+            dropped_links_count: span.links.dropped_count,
+            status: StatusRecord::from(&span.status),
+        }
+    }
+
+    fn into_span_data(self) -> std::result::Result<SpanData, String> {
+        let mut events = SpanEvents::default();
+        events.events = self
+            .events
+            .into_iter()
+            .map(EventRecord::into_event)
+            .collect();
+        events.dropped_count = self.dropped_events_count;
+
+        let mut links = SpanLinks::default();
+        links.links = self
+            .links
+            .into_iter()
+            .map(LinkRecord::into_link)
+            .collect::<std::result::Result<_, _>>()?;
+        links.dropped_count = self.dropped_links_count;
+
+        let parent_span_id = match &self.parent_span_id {
+            Some(id) => parse_span_id(id)?,
+            None => SpanId::INVALID,
+        };
+
+        Ok(SpanData {
+            span_context: span_context(
+                &self.trace_id,
+                &self.span_id,
+                self.trace_flags,
+                &self.trace_state,
+                self.is_remote,
+            )?,
+            parent_span_id,
+            parent_span_is_remote: self.parent_span_is_remote,
+            span_kind: self.kind.into(),
+            name: self.name.into(),
+            start_time: from_nanos(self.start_time_unix_nano),
+            end_time: from_nanos(self.end_time_unix_nano),
+            attributes: key_values(self.attributes),
+            dropped_attributes_count: self.dropped_attributes_count,
+            events,
+            links,
+            status: self.status.into(),
+            instrumentation_scope: self.scope.into_scope(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ResourceRecord {
+    attributes: Vec<AttributeRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema_url: Option<String>,
+}
+
+impl ResourceRecord {
+    fn new(resource: &Resource) -> Self {
+        let mut attributes: Vec<AttributeRecord> = resource
+            .iter()
+            .map(|(key, value)| AttributeRecord::new(key.as_str(), value))
+            .collect();
+        // The resource iterates in hash order; sort to compare resources
+        attributes.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Self {
+            attributes,
+            schema_url: resource.schema_url().map(str::to_string),
+        }
+    }
+
+    fn to_resource(&self) -> Resource {
+        let attributes = key_values(self.attributes.clone());
+        match &self.schema_url {
+            Some(schema_url) => Resource::builder_empty()
+                .with_schema_url(attributes, schema_url.clone())
+                .build(),
+            None => Resource::builder_empty()
+                .with_attributes(attributes)
+                .build(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ScopeRecord {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attributes: Vec<AttributeRecord>,
+}
+
+impl ScopeRecord {
+    fn new(scope: &InstrumentationScope) -> Self {
+        Self {
+            name: scope.name().to_string(),
+            version: scope.version().map(str::to_string),
+            schema_url: scope.schema_url().map(str::to_string),
+            attributes: scope
+                .attributes()
+                .map(|kv| AttributeRecord::new(kv.key.as_str(), &kv.value))
+                .collect(),
+        }
+    }
+
+    fn into_scope(self) -> InstrumentationScope {
+        let mut builder =
+            InstrumentationScope::builder(self.name).with_attributes(key_values(self.attributes));
+        if let Some(version) = self.version {
+            builder = builder.with_version(version);
+        }
+        if let Some(schema_url) = self.schema_url {
+            builder = builder.with_schema_url(schema_url);
+        }
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct AttributeRecord {
+    key: String,
+    value: ValueRecord,
+}
+
+impl AttributeRecord {
+    fn new(key: &str, value: &Value) -> Self {
+        Self {
+            key: key.to_string(),
+            value: ValueRecord::from(value),
+        }
+    }
+}
+
+/// A typed attribute value, so values keep their type when read back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ValueRecord {
+    Bool(bool),
+    Int(i64),
+    Double(Double),
+    String(String),
+    BoolArray(Vec<bool>),
+    IntArray(Vec<i64>),
+    DoubleArray(Vec<Double>),
+    StringArray(Vec<String>),
+}
+
+impl From<&Value> for ValueRecord {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Bool(v) => Self::Bool(*v),
+            Value::I64(v) => Self::Int(*v),
+            Value::F64(v) => Self::Double(Double(*v)),
+            Value::String(v) => Self::String(v.to_string()),
+            Value::Array(Array::Bool(v)) => Self::BoolArray(v.clone()),
+            Value::Array(Array::I64(v)) => Self::IntArray(v.clone()),
+            Value::Array(Array::F64(v)) => {
+                Self::DoubleArray(v.iter().copied().map(Double).collect())
+            }
+            Value::Array(Array::String(v)) => {
+                Self::StringArray(v.iter().map(ToString::to_string).collect())
+            }
+            other => Self::String(other.as_str().into_owned()),
+        }
+    }
+}
+
+impl From<ValueRecord> for Value {
+    fn from(value: ValueRecord) -> Self {
+        match value {
+            ValueRecord::Bool(v) => Value::Bool(v),
+            ValueRecord::Int(v) => Value::I64(v),
+            ValueRecord::Double(v) => Value::F64(v.0),
+            ValueRecord::String(v) => Value::String(v.into()),
+            ValueRecord::BoolArray(v) => Value::Array(Array::Bool(v)),
+            ValueRecord::IntArray(v) => Value::Array(Array::I64(v)),
+            ValueRecord::DoubleArray(v) => {
+                Value::Array(Array::F64(v.into_iter().map(|v| v.0).collect()))
+            }
+            ValueRecord::StringArray(v) => {
+                Value::Array(Array::String(v.into_iter().map(Into::into).collect()))
+            }
+        }
+    }
+}
+
+/// A double attribute value.
+///
+/// JSON numbers cannot hold `NaN` and the infinities, so these are written as
+/// the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Double(f64);
+
+impl Serialize for Double {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            v if v.is_nan() => serializer.serialize_str("NaN"),
+            f64::INFINITY => serializer.serialize_str("Infinity"),
+            f64::NEG_INFINITY => serializer.serialize_str("-Infinity"),
+            v => serializer.serialize_f64(v),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Double {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            String(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(v) => Ok(Self(v)),
+            Repr::String(v) => match v.as_str() {
+                "NaN" => Ok(Self(f64::NAN)),
+                "Infinity" => Ok(Self(f64::INFINITY)),
+                "-Infinity" => Ok(Self(f64::NEG_INFINITY)),
+                other => Err(de::Error::invalid_value(
+                    Unexpected::Str(other),
+                    &"a number, \"NaN\", \"Infinity\" or \"-Infinity\"",
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EventRecord {
+    name: String,
+    time_unix_nano: u64,
+    #[serde(default)]
+    attributes: Vec<AttributeRecord>,
+    #[serde(default)]
+    dropped_attributes_count: u32,
+}
+
+impl EventRecord {
+    fn new(event: &Event) -> Self {
+        Self {
+            name: event.name.to_string(),
+            time_unix_nano: to_nanos(event.timestamp),
+            attributes: attributes(&event.attributes),
+            dropped_attributes_count: event.dropped_attributes_count,
+        }
+    }
+
+    fn into_event(self) -> Event {
+        Event::new(
+            self.name,
+            from_nanos(self.time_unix_nano),
+            key_values(self.attributes),
+            self.dropped_attributes_count,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkRecord {
+    trace_id: String,
+    span_id: String,
+    trace_flags: u8,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    trace_state: String,
+    #[serde(default)]
+    is_remote: bool,
+    #[serde(default)]
+    attributes: Vec<AttributeRecord>,
+    #[serde(default)]
+    dropped_attributes_count: u32,
+}
+
+impl LinkRecord {
+    fn new(link: &Link) -> Self {
+        let context = &link.span_context;
+        Self {
+            trace_id: context.trace_id().to_string(),
+            span_id: context.span_id().to_string(),
+            trace_flags: context.trace_flags().to_u8(),
+            trace_state: context.trace_state().header(),
+            is_remote: context.is_remote(),
+            attributes: attributes(&link.attributes),
+            dropped_attributes_count: link.dropped_attributes_count,
+        }
+    }
+
+    fn into_link(self) -> std::result::Result<Link, String> {
+        Ok(Link::new(
+            span_context(
+                &self.trace_id,
+                &self.span_id,
+                self.trace_flags,
+                &self.trace_state,
+                self.is_remote,
+            )?,
+            key_values(self.attributes),
+            self.dropped_attributes_count,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum KindRecord {
+    Client,
+    Server,
+    Producer,
+    Consumer,
+    Internal,
+}
+
+impl From<&SpanKind> for KindRecord {
+    fn from(kind: &SpanKind) -> Self {
+        match kind {
+            SpanKind::Client => Self::Client,
+            SpanKind::Server => Self::Server,
+            SpanKind::Producer => Self::Producer,
+            SpanKind::Consumer => Self::Consumer,
+            SpanKind::Internal => Self::Internal,
+        }
+    }
+}
+
+impl From<KindRecord> for SpanKind {
+    fn from(kind: KindRecord) -> Self {
+        match kind {
+            KindRecord::Client => SpanKind::Client,
+            KindRecord::Server => SpanKind::Server,
+            KindRecord::Producer => SpanKind::Producer,
+            KindRecord::Consumer => SpanKind::Consumer,
+            KindRecord::Internal => SpanKind::Internal,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+enum StatusRecord {
+    Unset,
+    Ok,
+    Error { description: String },
+}
+
+impl From<&Status> for StatusRecord {
+    fn from(status: &Status) -> Self {
+        match status {
+            Status::Unset => Self::Unset,
+            Status::Ok => Self::Ok,
+            Status::Error { description } => Self::Error {
+                description: description.to_string(),
+            },
+        }
+    }
+}
+
+impl From<StatusRecord> for Status {
+    fn from(status: StatusRecord) -> Self {
+        match status {
+            StatusRecord::Unset => Status::Unset,
+            StatusRecord::Ok => Status::Ok,
+            StatusRecord::Error { description } => Status::error(description),
+        }
+    }
+}
+
+fn attributes(attributes: &[KeyValue]) -> Vec<AttributeRecord> {
+    attributes
+        .iter()
+        .map(|kv| AttributeRecord::new(kv.key.as_str(), &kv.value))
+        .collect()
+}
+
+fn key_values(attributes: Vec<AttributeRecord>) -> Vec<KeyValue> {
+    attributes
+        .into_iter()
+        .map(|attribute| KeyValue::new(attribute.key, Value::from(attribute.value)))
+        .collect()
+}
+
+fn span_context(
+    trace_id: &str,
+    span_id: &str,
+    trace_flags: u8,
+    trace_state: &str,
+    is_remote: bool,
+) -> std::result::Result<SpanContext, String> {
+    let trace_id =
+        TraceId::from_hex(trace_id).map_err(|_| format!("invalid trace ID {:?}", trace_id))?;
+    let trace_state = trace_state
+        .parse::<TraceState>()
+        .map_err(|_| format!("invalid trace state {:?}", trace_state))?;
+
+    Ok(SpanContext::new(
+        trace_id,
+        parse_span_id(span_id)?,
+        TraceFlags::new(trace_flags),
+        is_remote,
+        trace_state,
+    ))
+}
+
+fn parse_span_id(span_id: &str) -> std::result::Result<SpanId, String> {
+    SpanId::from_hex(span_id).map_err(|_| format!("invalid span ID {:?}", span_id))
+}
+
+fn to_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+fn from_nanos(nanos: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{span_data, MockResponse, MockServer};
+    use opentelemetry::trace::SpanContext;
+
+    fn read_file(path: &Path) -> Result<Vec<(ResourceRecord, SpanData)>> {
+        let mut file = SpanFile::open(path)?;
+        let mut spans = Vec::new();
+        while let Some(span) = file.read_span()? {
+            spans.push(span);
+        }
+        Ok(spans)
+    }
+
+    fn exporter(directory: &Path) -> FileExporter {
+        let mut exporter = FileExporter::builder(directory).build().unwrap();
+        exporter.set_resource(
+            &Resource::builder_empty()
+                .with_attribute(KeyValue::new("service.name", "offline-eval"))
+                .build(),
+        );
+        exporter
+    }
+
+    #[tokio::test]
+    async fn test_spans_round_trip_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let exporter = exporter(dir.path());
+
+        let mut span = span_data(
+            0xabc,
+            2,
+            1,
+            vec![
+                KeyValue::new("langfuse.observation.type", "generation"),
+                KeyValue::new("gen_ai.usage.input_tokens", 42),
+                KeyValue::new("temperature", 0.7),
+                KeyValue::new(
+                    "langfuse.trace.tags",
+                    Value::Array(Array::String(vec!["a".into(), "b".into()])),
+                ),
+            ],
+        );
+        span.status = Status::error("boom");
+        span.events.events.push(Event::new(
+            "retry",
+            span.start_time,
+            vec![KeyValue::new("attempt", 2)],
+            0,
+        ));
+        span.links.links.push(Link::with_context(SpanContext::new(
+            TraceId::from(7),
+            SpanId::from(8),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        )));
+        exporter.export(vec![span.clone()]).await.unwrap();
+
+        let files = span_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        let spans = read_file(&files[0]).unwrap();
+        assert_eq!(spans.len(), 1);
+
+        let (resource, read) = &spans[0];
+        assert_eq!(
+            resource.to_resource().get(&"service.name".into()),
+            Some(Value::from("offline-eval"))
+        );
+        assert_eq!(read.span_context, span.span_context);
+        assert_eq!(read.parent_span_id, span.parent_span_id);
+        assert_eq!(read.name, span.name);
+        assert_eq!(read.attributes, span.attributes);
+        assert_eq!(read.events, span.events);
+        assert_eq!(read.links, span.links);
+        assert_eq!(read.status, span.status);
+        assert_eq!(read.instrumentation_scope, span.instrumentation_scope);
+        assert_eq!(to_nanos(read.start_time), to_nanos(span.start_time));
+    }
+
+    #[tokio::test]
+    async fn test_rotates_files_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let exporter = FileExporter::builder(dir.path())
+            .with_max_file_size(100)
+            .build()
+            .unwrap();
+
+        for id in 1..=3 {
+            exporter
+                .export(vec![span_data(1, id, 0, vec![])])
+                .await
+                .unwrap();
+        }
+
+        let files = span_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(file_sequence(&files[2]), Some(2));
+
+        // A new exporter continues after the existing files
+        let exporter = FileExporter::builder(dir.path()).build().unwrap();
+        exporter
+            .export(vec![span_data(1, 4, 0, vec![])])
+            .await
+            .unwrap();
+        let files = span_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 4);
+        assert_eq!(read_file(&files[3]).unwrap()[0].1.name, "span-4");
+    }
+
+    #[tokio::test]
+    async fn test_upload_sends_spans_with_resource() {
+        let dir = tempfile::tempdir().unwrap();
+        let exporter = exporter(dir.path());
+        exporter
+            .export(vec![span_data(1, 1, 0, vec![]), span_data(1, 2, 1, vec![])])
+            .await
+            .unwrap();
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let summary = upload(
+            dir.path(),
+            ExporterBuilder::new()
+                .with_host(&server.url())
+                .with_basic_auth("pk-test", "sk-test"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary, UploadSummary { files: 1, spans: 2 });
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let contains = |needle: &str| {
+            requests[0]
+                .body
+                .windows(needle.len())
+                .any(|w| w == needle.as_bytes())
+        };
+        assert!(contains("offline-eval"));
+        assert!(contains("span-1"));
+        assert!(contains("span-2"));
+    }
+
+    #[tokio::test]
+    async fn test_upload_fails_on_export_error() {
+        let dir = tempfile::tempdir().unwrap();
+        exporter(dir.path())
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        let server = MockServer::start(vec![MockResponse::status(400)]);
+        let result = upload(
+            dir.path(),
+            ExporterBuilder::new()
+                .with_host(&server.url())
+                .with_basic_auth("pk-test", "sk-test"),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Export(_))));
+    }
+
+    #[test]
+    fn test_invalid_line_reports_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = file_path(dir.path(), 0);
+        fs::write(&path, "\nnot json\n").unwrap();
+
+        let result = read_file(&path);
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidSpanFile { line: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_last_line_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        exporter(dir.path())
+            .export(vec![span_data(1, 1, 0, vec![]), span_data(1, 2, 1, vec![])])
+            .await
+            .unwrap();
+        // A crash in the middle of writing the last line
+        let path = file_path(dir.path(), 0);
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 10]).unwrap();
+        let mut next = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path(dir.path(), 1))
+            .unwrap();
+        next.write_all(&data[..data.len() / 2 + 1]).unwrap();
+
+        let server = MockServer::start(vec![MockResponse::status(200), MockResponse::status(200)]);
+        let summary = upload(
+            dir.path(),
+            ExporterBuilder::new()
+                .with_host(&server.url())
+                .with_basic_auth("pk-test", "sk-test"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary, UploadSummary { files: 2, spans: 2 });
+    }
+
+    #[test]
+    fn test_batches_share_a_resource() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = file_path(dir.path(), 0);
+        let record = |resource: &Resource, id| {
+            let span = span_data(1, id, 0, vec![]);
+            let mut line =
+                serde_json::to_vec(&SpanRecord::new(&span, &ResourceRecord::new(resource)))
+                    .unwrap();
+            line.push(b'\n');
+            line
+        };
+        let first = Resource::builder_empty().with_service_name("first").build();
+        let second = Resource::builder_empty()
+            .with_service_name("second")
+            .build();
+        let lines = [
+            record(&first, 1),
+            record(&first, 2),
+            record(&first, 3),
+            record(&second, 4),
+        ];
+        fs::write(&path, lines.concat()).unwrap();
+
+        let mut file = SpanFile::open(&path).unwrap();
+        let sizes: Vec<usize> = std::iter::from_fn(|| file.read_batch(2).unwrap())
+            .map(|(_, batch)| batch.len())
+            .collect();
+
+        assert_eq!(sizes, [2, 1, 1]);
+    }
+
+    #[test]
+    fn test_non_finite_doubles_round_trip() {
+        let values = [
+            Value::F64(f64::INFINITY),
+            Value::F64(f64::NEG_INFINITY),
+            Value::F64(1.5),
+            Value::Array(Array::F64(vec![f64::NAN, 2.0])),
+        ];
+
+        for value in values {
+            let json = serde_json::to_string(&ValueRecord::from(&value)).unwrap();
+            let record: ValueRecord = serde_json::from_str(&json).unwrap();
+            match (Value::from(record), &value) {
+                (Value::Array(Array::F64(read)), Value::Array(Array::F64(_))) => {
+                    assert!(read[0].is_nan());
+                    assert_eq!(read[1], 2.0);
+                }
+                (read, _) => assert_eq!(read, value),
+            }
+        }
+
+        assert_eq!(
+            serde_json::to_string(&ValueRecord::from(&Value::F64(f64::NAN))).unwrap(),
+            r#"{"double":"NaN"}"#
+        );
+        assert!(serde_json::from_str::<ValueRecord>(r#"{"double":"nan"}"#).is_err());
+    }
+}
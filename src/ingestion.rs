@@ -0,0 +1,674 @@
+//! Transport sending spans to the Langfuse ingestion API.
+//!
+//! Older self-hosted Langfuse versions do not provide the OTLP endpoint, but
+//! all of them accept batches of events on `/api/public/ingestion`. Finished
+//! spans are mapped to these events as follows:
+//!
+//! - every trace in a batch gets a `trace-create` event, carrying the name of
+//!   its root span and the `langfuse.trace.*`, `langfuse.session.id` and
+//!   `langfuse.user.id` attributes found on any of its spans
+//! - spans with `langfuse.observation.type` set to `generation`, or carrying a
+//!   `gen_ai.request.model` or `gen_ai.response.model` attribute, become
+//!   `generation-create` events, all other spans `span-create` events
+//! - span events become `event-create` events nested under their span
+//!
+//! Attributes that are not mapped to a dedicated field are sent as the
+//! metadata of the observation, along with the resource attributes under
+//! `resourceAttributes`.
+//!
+//! Event IDs are derived from the trace ID, span ID and event index, so a batch
+//! sent again, e.g. by a spool replay or a repeated upload, does not create
+//! duplicate events.
+
+use crate::context::attributes;
+use crate::transport::LangfuseHttpClient;
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use http::header::CONTENT_TYPE;
+use http::{Method, Request};
+use opentelemetry::trace::{SpanId, Status, TraceId};
+use opentelemetry::{Array, KeyValue, Value};
+use opentelemetry_http::HttpClient;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use serde::Deserialize;
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+const OBSERVATION_TYPE: &str = "langfuse.observation.type";
+const OBSERVATION_INPUT: &str = "langfuse.observation.input";
+const OBSERVATION_OUTPUT: &str = "langfuse.observation.output";
+const OBSERVATION_MODEL: &str = "langfuse.observation.model.name";
+const TRACE_INPUT: &str = "langfuse.trace.input";
+const TRACE_OUTPUT: &str = "langfuse.trace.output";
+const SESSION_ID: &str = "session.id";
+const USER_ID: &str = "user.id";
+const GEN_AI_REQUEST_MODEL: &str = "gen_ai.request.model";
+const GEN_AI_RESPONSE_MODEL: &str = "gen_ai.response.model";
+const GEN_AI_INPUT_TOKENS: &str = "gen_ai.usage.input_tokens";
+const GEN_AI_OUTPUT_TOKENS: &str = "gen_ai.usage.output_tokens";
+
+/// Metadata key holding the resource attributes.
+const RESOURCE_ATTRIBUTES: &str = "resourceAttributes";
+
+/// Size of the JSON envelope of a request, without events.
+pub(crate) const ENVELOPE_SIZE: usize = r#"{"batch":[]}"#.len();
+
+/// Attributes mapped to dedicated fields, which are left out of the metadata.
+const MAPPED_ATTRIBUTES: &[&str] = &[
+    attributes::TRACE_SESSION_ID,
+    attributes::TRACE_USER_ID,
+    attributes::TRACE_TAGS,
+    attributes::TRACE_METADATA,
+    attributes::TRACE_NAME,
+    TRACE_INPUT,
+    TRACE_OUTPUT,
+    SESSION_ID,
+    USER_ID,
+    OBSERVATION_TYPE,
+    OBSERVATION_INPUT,
+    OBSERVATION_OUTPUT,
+    OBSERVATION_MODEL,
+    GEN_AI_REQUEST_MODEL,
+    GEN_AI_RESPONSE_MODEL,
+    GEN_AI_INPUT_TOKENS,
+    GEN_AI_OUTPUT_TOKENS,
+];
+
+/// Exports spans as batches of Langfuse ingestion events.
+#[derive(Debug)]
+pub(crate) struct IngestionExporter {
+    client: LangfuseHttpClient,
+    endpoint: String,
+    headers: HashMap<String, String>,
+    resource: Map<String, JsonValue>,
+}
+
+/// Response of the ingestion API, listing the events it rejected.
+#[derive(Debug, Deserialize)]
+struct IngestionResponse {
+    #[serde(default)]
+    errors: Vec<IngestionError>,
+}
+
+/// An event rejected by the ingestion API.
+#[derive(Debug, Deserialize)]
+struct IngestionError {
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl IngestionExporter {
+    /// Creates an exporter posting to the ingestion `endpoint` with `headers`.
+    pub(crate) fn new(
+        client: LangfuseHttpClient,
+        endpoint: String,
+        headers: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            client,
+            endpoint,
+            headers,
+            resource: Map::new(),
+        }
+    }
+
+    /// Sets the resource whose attributes are added to every observation.
+    pub(crate) fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource_attributes(resource);
+    }
+
+    /// Maps a batch of spans to ingestion events, created at `now`.
+    pub(crate) fn events(&self, batch: &[SpanData], now: SystemTime) -> Vec<JsonValue> {
+        ingestion_events(batch, &self.resource, now)
+    }
+
+    /// Sends `batch` in a single ingestion request.
+    pub(crate) async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let events = self.events(&batch, SystemTime::now());
+        let body = serde_json::to_vec(&json!({ "batch": events }))
+            .map_err(|err| OTelSdkError::InternalFailure(err.to_string()))?;
+
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.endpoint)
+            .header(CONTENT_TYPE, "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let request = request
+            .body(Bytes::from(body))
+            .map_err(|err| OTelSdkError::InternalFailure(err.to_string()))?;
+
+        let response = self.client.send_bytes(request).await.map_err(|err| {
+            OTelSdkError::InternalFailure(format!("ingestion request failed: {}", err))
+        })?;
+        if !response.status().is_success() {
+            return Err(OTelSdkError::InternalFailure(format!(
+                "ingestion request failed with status {}",
+                response.status()
+            )));
+        }
+
+        // Langfuse answers with 207 Multi-Status, listing the rejected events
+        let errors = serde_json::from_slice::<IngestionResponse>(response.body())
+            .map(|response| response.errors)
+            .unwrap_or_default();
+        match errors.first() {
+            None => Ok(()),
+            Some(first) => Err(OTelSdkError::InternalFailure(format!(
+                "Langfuse rejected {} of {} ingestion events: {}",
+                errors.len(),
+                events.len(),
+                first.message.as_deref().unwrap_or("unknown error")
+            ))),
+        }
+    }
+}
+
+/// Returns the size the resource attributes add to the observation of a span.
+pub(crate) fn resource_size(resource: &Resource) -> usize {
+    let attributes = resource_attributes(resource);
+    if attributes.is_empty() {
+        return 0;
+    }
+    let metadata = json!({ "metadata": { RESOURCE_ATTRIBUTES: attributes } });
+    serde_json::to_vec(&metadata).map_or(0, |json| json.len())
+}
+
+/// Returns the size of the events of a span in a request, including the
+/// `trace-create` event it adds to its trace, if any, and the separators, but
+/// without the resource attributes.
+pub(crate) fn span_size(span: &SpanData) -> usize {
+    ingestion_events(
+        std::slice::from_ref(span),
+        &Map::new(),
+        SystemTime::UNIX_EPOCH,
+    )
+    .iter()
+    .map(|event| serde_json::to_vec(event).map_or(0, |json| json.len()) + 1)
+    .sum()
+}
+
+/// Returns the resource attributes, as added to the metadata of observations.
+fn resource_attributes(resource: &Resource) -> Map<String, JsonValue> {
+    resource
+        .iter()
+        .map(|(key, value)| (key.to_string(), json_value(value)))
+        .collect()
+}
+
+/// Maps a batch of spans to ingestion events, created at `now`.
+///
+/// `resource` holds the resource attributes added to every observation.
+fn ingestion_events(
+    batch: &[SpanData],
+    resource: &Map<String, JsonValue>,
+    now: SystemTime,
+) -> Vec<JsonValue> {
+    // Trace bodies with the ID of their `trace-create` event
+    let mut traces: Vec<(String, Map<String, JsonValue>)> = Vec::new();
+    let mut trace_index: HashMap<TraceId, usize> = HashMap::new();
+    let mut observations = Vec::new();
+
+    for span in batch {
+        let trace_id = span.span_context.trace_id();
+        let span_id = span.span_context.span_id();
+        let index = *trace_index.entry(trace_id).or_insert_with(|| {
+            let mut trace = Map::new();
+            trace.insert("id".into(), json!(trace_id.to_string()));
+            // The first span of the trace identifies the event within the batch
+            traces.push((event_id(trace_id, span_id, "trace"), trace));
+            traces.len() - 1
+        });
+        merge_trace(&mut traces[index].1, span);
+
+        let mut body = observation(span);
+        if !resource.is_empty() {
+            let metadata = body
+                .entry("metadata")
+                .or_insert_with(|| JsonValue::Object(Map::new()));
+            if let JsonValue::Object(metadata) = metadata {
+                metadata.insert(
+                    RESOURCE_ATTRIBUTES.into(),
+                    JsonValue::Object(resource.clone()),
+                );
+            }
+        }
+        observations.push(event(
+            event_id(trace_id, span_id, "observation"),
+            observation_type(span),
+            body,
+            now,
+        ));
+        for (index, span_event) in span.events.iter().enumerate() {
+            let mut body = Map::new();
+            body.insert(
+                "id".into(),
+                json!(event_id(trace_id, span_id, &index.to_string())),
+            );
+            body.insert("traceId".into(), json!(trace_id.to_string()));
+            body.insert(
+                "parentObservationId".into(),
+                json!(span.span_context.span_id().to_string()),
+            );
+            body.insert("name".into(), json!(span_event.name));
+            body.insert("startTime".into(), json!(format_time(span_event.timestamp)));
+            insert_metadata(&mut body, &span_event.attributes, &[]);
+            observations.push(event(
+                event_id(trace_id, span_id, &format!("event-{}", index)),
+                "event-create",
+                body,
+                now,
+            ));
+        }
+    }
+
+    // Without the root span or trace attributes, a `trace-create` event would
+    // only carry the ID, and its event timestamp could become the one of the
+    // trace. Langfuse creates the trace of an observation by itself.
+    traces
+        .into_iter()
+        .filter(|(_, trace)| trace.len() > 1)
+        .map(|(id, trace)| event(id, "trace-create", trace, now))
+        .chain(observations)
+        .collect()
+}
+
+/// Wraps an event body into an ingestion event.
+fn event(id: String, event_type: &str, body: Map<String, JsonValue>, now: SystemTime) -> JsonValue {
+    json!({
+        "id": id,
+        "type": event_type,
+        "timestamp": format_time(now),
+        "body": body,
+    })
+}
+
+/// Adds the trace-level information found on `span` to a trace body.
+fn merge_trace(trace: &mut Map<String, JsonValue>, span: &SpanData) {
+    if span.parent_span_id == SpanId::INVALID {
+        trace.entry("name").or_insert(json!(span.name));
+        trace.insert("timestamp".into(), json!(format_time(span.start_time)));
+    }
+
+    for KeyValue { key, value, .. } in &span.attributes {
+        let field = match key.as_str() {
+            attributes::TRACE_NAME => "name",
+            attributes::TRACE_SESSION_ID => "sessionId",
+            attributes::TRACE_USER_ID => "userId",
+            attributes::TRACE_TAGS => "tags",
+            attributes::TRACE_METADATA => "metadata",
+            TRACE_INPUT => "input",
+            TRACE_OUTPUT => "output",
+            // The generic keys only apply if the Langfuse ones are absent
+            SESSION_ID if !trace.contains_key("sessionId") => "sessionId",
+            USER_ID if !trace.contains_key("userId") => "userId",
+            _ => continue,
+        };
+        let value = match field {
+            "name" | "sessionId" | "userId" => json!(value.as_str()),
+            _ => parse_json(value),
+        };
+        trace.insert(field.into(), value);
+    }
+}
+
+/// Returns the ingestion event type of a span.
+fn observation_type(span: &SpanData) -> &'static str {
+    let declared = attribute(span, OBSERVATION_TYPE).map(|value| value.as_str());
+    match declared.as_deref() {
+        Some("generation") => "generation-create",
+        Some("event") => "event-create",
+        Some(_) => "span-create",
+        None if attribute(span, GEN_AI_REQUEST_MODEL).is_some()
+            || attribute(span, GEN_AI_RESPONSE_MODEL).is_some() =>
+        {
+            "generation-create"
+        }
+        None => "span-create",
+    }
+}
+
+/// Maps a span to the body of its observation event.
+fn observation(span: &SpanData) -> Map<String, JsonValue> {
+    let mut body = Map::new();
+    body.insert("id".into(), json!(span.span_context.span_id().to_string()));
+    body.insert(
+        "traceId".into(),
+        json!(span.span_context.trace_id().to_string()),
+    );
+    if span.parent_span_id != SpanId::INVALID {
+        body.insert(
+            "parentObservationId".into(),
+            json!(span.parent_span_id.to_string()),
+        );
+    }
+    body.insert("name".into(), json!(span.name));
+    body.insert("startTime".into(), json!(format_time(span.start_time)));
+    body.insert("endTime".into(), json!(format_time(span.end_time)));
+
+    if let Some(input) = attribute(span, OBSERVATION_INPUT) {
+        body.insert("input".into(), parse_json(input));
+    }
+    if let Some(output) = attribute(span, OBSERVATION_OUTPUT) {
+        body.insert("output".into(), parse_json(output));
+    }
+    if let Status::Error { description } = &span.status {
+        body.insert("level".into(), json!("ERROR"));
+        body.insert("statusMessage".into(), json!(description));
+    }
+
+    if observation_type(span) == "generation-create" {
+        let model = attribute(span, OBSERVATION_MODEL)
+            .or_else(|| attribute(span, GEN_AI_RESPONSE_MODEL))
+            .or_else(|| attribute(span, GEN_AI_REQUEST_MODEL));
+        if let Some(model) = model {
+            body.insert("model".into(), json!(model.as_str()));
+        }
+
+        let mut usage = Map::new();
+        for (key, field) in [
+            (GEN_AI_INPUT_TOKENS, "input"),
+            (GEN_AI_OUTPUT_TOKENS, "output"),
+        ] {
+            if let Some(tokens) = attribute(span, key) {
+                usage.insert(field.into(), json_value(tokens));
+            }
+        }
+        if !usage.is_empty() {
+            usage.insert("unit".into(), json!("TOKENS"));
+            body.insert("usage".into(), JsonValue::Object(usage));
+        }
+    }
+
+    insert_metadata(&mut body, &span.attributes, MAPPED_ATTRIBUTES);
+    body
+}
+
+/// Adds the attributes not listed in `mapped` as the metadata of an observation.
+fn insert_metadata(body: &mut Map<String, JsonValue>, attributes: &[KeyValue], mapped: &[&str]) {
+    let metadata: Map<String, JsonValue> = attributes
+        .iter()
+        .filter(|kv| !mapped.contains(&kv.key.as_str()))
+        .map(|kv| (kv.key.to_string(), json_value(&kv.value)))
+        .collect();
+    if !metadata.is_empty() {
+        body.insert("metadata".into(), JsonValue::Object(metadata));
+    }
+}
+
+/// Returns the value of the span attribute `key`.
+fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+/// Converts an attribute value to JSON, decoding strings holding JSON
+/// objects or arrays, such as tags and metadata.
+fn parse_json(value: &Value) -> JsonValue {
+    if let Value::String(string) = value {
+        if let Ok(parsed @ (JsonValue::Object(_) | JsonValue::Array(_))) =
+            serde_json::from_str(string.as_str())
+        {
+            return parsed;
+        }
+    }
+    json_value(value)
+}
+
+/// Converts an attribute value to JSON.
+fn json_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Bool(value) => json!(value),
+        Value::I64(value) => json!(value),
+        Value::F64(value) => json!(value),
+        Value::String(value) => json!(value.as_str()),
+        Value::Array(Array::Bool(values)) => json!(values),
+        Value::Array(Array::I64(values)) => json!(values),
+        Value::Array(Array::F64(values)) => json!(values),
+        Value::Array(Array::String(values)) => {
+            JsonValue::Array(values.iter().map(|value| json!(value.as_str())).collect())
+        }
+        other => json!(other.to_string()),
+    }
+}
+
+/// Formats a timestamp as RFC 3339, as expected by the ingestion API.
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Returns the identifier of an ingestion event about a span.
+///
+/// The same span always yields the same identifiers, which lets Langfuse
+/// deduplicate events that are sent again.
+fn event_id(trace_id: TraceId, span_id: SpanId, suffix: &str) -> String {
+    format!("{}-{}-{}", trace_id, span_id, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{span_data, MockResponse, MockServer};
+    use opentelemetry::trace::Event;
+    use opentelemetry_sdk::trace::SpanEvents;
+
+    fn exporter(server: &MockServer) -> IngestionExporter {
+        IngestionExporter::new(
+            LangfuseHttpClient::new(reqwest::Client::new()),
+            format!("{}/api/public/ingestion", server.url()),
+            HashMap::from([("Authorization".to_string(), "Basic test".to_string())]),
+        )
+    }
+
+    #[test]
+    fn test_maps_spans_to_ingestion_events() {
+        let root = span_data(
+            1,
+            1,
+            0,
+            vec![
+                KeyValue::new(attributes::TRACE_SESSION_ID, "session-1"),
+                KeyValue::new(attributes::TRACE_TAGS, r#"["a","b"]"#),
+                KeyValue::new("http.method", "GET"),
+            ],
+        );
+        let mut generation = span_data(
+            1,
+            2,
+            1,
+            vec![
+                KeyValue::new(GEN_AI_REQUEST_MODEL, "gpt-4o"),
+                KeyValue::new(GEN_AI_INPUT_TOKENS, 10),
+                KeyValue::new(GEN_AI_OUTPUT_TOKENS, 20),
+                KeyValue::new(OBSERVATION_INPUT, r#"{"prompt":"hi"}"#),
+                KeyValue::new(attributes::TRACE_USER_ID, "user-1"),
+            ],
+        );
+        generation.status = Status::error("boom");
+        let mut events = SpanEvents::default();
+        events.events.push(Event::new(
+            "retry",
+            SystemTime::now(),
+            vec![KeyValue::new("attempt", 2)],
+            0,
+        ));
+        generation.events = events;
+
+        let events = ingestion_events(&[root, generation], &Map::new(), SystemTime::now());
+
+        let types: Vec<_> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            types,
+            [
+                "trace-create",
+                "span-create",
+                "generation-create",
+                "event-create"
+            ]
+        );
+
+        let trace = &events[0]["body"];
+        assert_eq!(trace["id"], format!("{:032x}", 1));
+        assert_eq!(trace["name"], "span-1");
+        assert_eq!(trace["sessionId"], "session-1");
+        assert_eq!(trace["userId"], "user-1");
+        assert_eq!(trace["tags"], json!(["a", "b"]));
+
+        let span = &events[1]["body"];
+        assert!(span.get("parentObservationId").is_none());
+        assert_eq!(span["metadata"], json!({ "http.method": "GET" }));
+
+        let generation = &events[2]["body"];
+        assert_eq!(generation["id"], format!("{:016x}", 2));
+        assert_eq!(generation["parentObservationId"], format!("{:016x}", 1));
+        assert_eq!(generation["model"], "gpt-4o");
+        assert_eq!(
+            generation["usage"],
+            json!({ "input": 10, "output": 20, "unit": "TOKENS" })
+        );
+        assert_eq!(generation["input"], json!({ "prompt": "hi" }));
+        assert_eq!(generation["level"], "ERROR");
+        assert_eq!(generation["statusMessage"], "boom");
+        assert!(generation.get("metadata").is_none());
+
+        let event = &events[3]["body"];
+        assert_eq!(event["name"], "retry");
+        assert_eq!(event["parentObservationId"], format!("{:016x}", 2));
+        assert_eq!(event["metadata"], json!({ "attempt": 2 }));
+    }
+
+    #[test]
+    fn test_event_ids_are_stable_and_unique() {
+        let mut span = span_data(1, 1, 0, vec![]);
+        let mut events = SpanEvents::default();
+        for name in ["first", "second"] {
+            events
+                .events
+                .push(Event::new(name, SystemTime::now(), vec![], 0));
+        }
+        span.events = events;
+        let batch = [span, span_data(1, 2, 1, vec![])];
+
+        let ids = |events: Vec<JsonValue>| -> Vec<String> {
+            events
+                .iter()
+                .flat_map(|event| [&event["id"], &event["body"]["id"]])
+                .map(|id| id.as_str().unwrap().to_string())
+                .collect()
+        };
+        let first = ids(ingestion_events(&batch, &Map::new(), SystemTime::now()));
+        let again = ids(ingestion_events(&batch, &Map::new(), SystemTime::now()));
+        assert_eq!(first, again);
+
+        // Trace and observation bodies carry the OTel IDs, all else is unique
+        let mut envelope_ids: Vec<_> = first.iter().step_by(2).collect();
+        envelope_ids.sort();
+        envelope_ids.dedup();
+        assert_eq!(envelope_ids.len(), 5);
+        assert_ne!(first[5], first[7]);
+    }
+
+    #[test]
+    fn test_trace_is_only_created_with_trace_information() {
+        let types = |batch: &[SpanData]| -> Vec<String> {
+            ingestion_events(batch, &Map::new(), SystemTime::now())
+                .iter()
+                .map(|event| event["type"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Child spans exported before their root
+        assert_eq!(types(&[span_data(1, 2, 1, vec![])]), ["span-create"]);
+        assert_eq!(
+            types(&[span_data(
+                1,
+                2,
+                1,
+                vec![KeyValue::new(attributes::TRACE_USER_ID, "user-1")]
+            )]),
+            ["trace-create", "span-create"]
+        );
+        assert_eq!(
+            types(&[span_data(1, 1, 0, vec![])]),
+            ["trace-create", "span-create"]
+        );
+    }
+
+    #[test]
+    fn test_resource_attributes_are_added_to_observations() {
+        let resource = Resource::builder_empty()
+            .with_attribute(KeyValue::new("service.name", "checkout"))
+            .build();
+        let span = span_data(1, 1, 0, vec![KeyValue::new("http.method", "GET")]);
+
+        let events = ingestion_events(&[span], &resource_attributes(&resource), SystemTime::now());
+
+        assert!(events[0]["body"].get("metadata").is_none());
+        assert_eq!(
+            events[1]["body"]["metadata"],
+            json!({
+                "http.method": "GET",
+                "resourceAttributes": { "service.name": "checkout" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_declared_observation_type_takes_precedence() {
+        let span = span_data(
+            1,
+            1,
+            0,
+            vec![
+                KeyValue::new(OBSERVATION_TYPE, "span"),
+                KeyValue::new(GEN_AI_REQUEST_MODEL, "gpt-4o"),
+            ],
+        );
+        assert_eq!(observation_type(&span), "span-create");
+
+        let span = span_data(1, 1, 0, vec![KeyValue::new(OBSERVATION_TYPE, "generation")]);
+        assert_eq!(observation_type(&span), "generation-create");
+    }
+
+    #[tokio::test]
+    async fn test_posts_batch_to_ingestion_endpoint() {
+        let server = MockServer::start(vec![MockResponse::status(207)
+            .with_body(r#"{"successes":[{"id":"1","status":201}],"errors":[]}"#)]);
+
+        exporter(&server)
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/api/public/ingestion");
+        assert_eq!(requests[0].header("authorization"), Some("Basic test"));
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        let body: JsonValue = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["batch"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_events_fail_the_export() {
+        let server = MockServer::start(vec![MockResponse::status(207).with_body(
+            r#"{"successes":[],"errors":[{"id":"1","status":400,"message":"Invalid request data"}]}"#,
+        )]);
+
+        let result = exporter(&server)
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await;
+
+        let Err(OTelSdkError::InternalFailure(message)) = result else {
+            panic!("expected the export to fail");
+        };
+        assert!(message.contains("rejected 1 of 2"));
+        assert!(message.contains("Invalid request data"));
+    }
+}
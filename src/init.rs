@@ -0,0 +1,406 @@
+//! One-call installation of a tracer provider exporting to Langfuse.
+//!
+//! [`init`] and [`init_with`] build the Langfuse exporter, wrap it in a batch
+//! span processor, install the resulting tracer provider as the global one and
+//! return a [`TracingGuard`]. Dropping the guard flushes the pending spans and
+//! shuts the provider down within a deadline, so short-lived binaries do not
+//! lose their last traces.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry::global;
+//! use opentelemetry::trace::Tracer;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // Requires: LANGFUSE_PUBLIC_KEY, LANGFUSE_SECRET_KEY
+//! let _guard = opentelemetry_langfuse::init()?;
+//!
+//! global::tracer("my-service").in_span("work", |_| {
+//!     // ... your code here ...
+//! });
+//!
+//! // Spans are flushed when the guard goes out of scope
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, ExporterBuilder, LangfuseConfig, Result};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::runtime::{Tokio, TokioCurrentThread};
+use opentelemetry_sdk::trace::{
+    span_processor_with_async_runtime, BatchConfig, BatchSpanProcessor, SdkTracerProvider,
+};
+use opentelemetry_sdk::Resource;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Default deadline for flushing and shutting down the tracer provider.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration of [`init_with`].
+pub struct InitConfig {
+    exporter: Option<ExporterBuilder>,
+    service_name: Option<String>,
+    resource_attributes: Vec<KeyValue>,
+    batch_config: Option<BatchConfig>,
+    shutdown_timeout: Duration,
+}
+
+impl InitConfig {
+    /// Creates a configuration with the defaults of [`init`].
+    pub fn new() -> Self {
+        Self {
+            exporter: None,
+            service_name: None,
+            resource_attributes: Vec::new(),
+            batch_config: None,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+
+    /// Creates a configuration with the exporter and batch settings of a
+    /// [`LangfuseConfig`].
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The exporter configuration, e.g. loaded from a file
+    pub fn from_config(config: &LangfuseConfig) -> Result<Self> {
+        Ok(Self::new()
+            .with_exporter(ExporterBuilder::from_config(config)?)
+            .with_batch_config(config.batch_config()?))
+    }
+
+    /// Sets the exporter configuration.
+    ///
+    /// Defaults to [`ExporterBuilder::from_env`].
+    ///
+    /// # Arguments
+    ///
+    /// * `exporter` - The exporter builder to build the exporter from
+    pub fn with_exporter(mut self, exporter: ExporterBuilder) -> Self {
+        self.exporter = Some(exporter);
+        self
+    }
+
+    /// Sets the `service.name` resource attribute.
+    ///
+    /// Defaults to `OTEL_SERVICE_NAME`, or the SDK default if unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The service name
+    pub fn with_service_name(mut self, name: impl Into<String>) -> Self {
+        self.service_name = Some(name.into());
+        self
+    }
+
+    /// Adds resource attributes, e.g. `service.version`.
+    ///
+    /// # Arguments
+    ///
+    /// * `attributes` - The resource attributes to add
+    pub fn with_resource_attributes(
+        mut self,
+        attributes: impl IntoIterator<Item = KeyValue>,
+    ) -> Self {
+        self.resource_attributes.extend(attributes);
+        self
+    }
+
+    /// Sets the batching configuration of the span processor.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The batch configuration
+    pub fn with_batch_config(mut self, config: BatchConfig) -> Self {
+        self.batch_config = Some(config);
+        self
+    }
+
+    /// Sets the deadline for flushing and shutting down when the guard is dropped.
+    ///
+    /// Spans that are not exported by then are lost. Defaults to 5 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The shutdown deadline
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+}
+
+impl Default for InitConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guard of the tracer provider installed by [`init`] or [`init_with`].
+///
+/// Dropping the guard force-flushes the pending spans and shuts the tracer
+/// provider down, giving up after the configured shutdown timeout. Keep it
+/// alive for as long as spans are recorded, typically in `main`.
+#[must_use = "dropping the guard shuts the tracer provider down"]
+#[derive(Debug)]
+pub struct TracingGuard {
+    provider: Option<SdkTracerProvider>,
+    shutdown_timeout: Duration,
+}
+
+impl TracingGuard {
+    /// Returns the installed tracer provider.
+    pub fn provider(&self) -> &SdkTracerProvider {
+        self.provider
+            .as_ref()
+            .expect("the provider is only taken on shutdown")
+    }
+
+    /// Flushes the pending spans and shuts the tracer provider down.
+    ///
+    /// Unlike dropping the guard, this reports whether the shutdown succeeded
+    /// within the deadline.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.shutdown_provider().map_err(Error::from)
+    }
+
+    fn shutdown_provider(&mut self) -> OTelSdkResult {
+        let Some(provider) = self.provider.take() else {
+            return Ok(());
+        };
+
+        // The batch processor of the async runtime ignores shutdown timeouts,
+        // so wait on a separate thread to enforce the deadline
+        let timeout = self.shutdown_timeout;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let flushed = provider.force_flush();
+            let shut_down = provider.shutdown_with_timeout(timeout);
+            let _ = sender.send(flushed.and(shut_down));
+        });
+        receiver
+            .recv_timeout(timeout)
+            .unwrap_or(Err(OTelSdkError::Timeout(timeout)))
+    }
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        let _ = self.shutdown_provider();
+    }
+}
+
+/// Installs a global tracer provider exporting to Langfuse, configured from
+/// environment variables.
+///
+/// Equivalent to [`init_with`] with the default [`InitConfig`], see
+/// [`ExporterBuilder::from_env`] for the environment variables.
+pub fn init() -> Result<TracingGuard> {
+    init_with(InitConfig::default())
+}
+
+/// Installs a global tracer provider exporting to Langfuse.
+///
+/// Within a Tokio runtime, spans are exported by the batch span processor of
+/// the async runtime. Outside of one, the thread-based batch span processor is
+/// used, which requires the exporter to be configured with a blocking HTTP
+/// client (`blocking-client` cargo feature).
+///
+/// The tracer provider samples with [`ExporterBuilder::sampler`]. When
+/// tracing is [disabled](ExporterBuilder::with_tracing_enabled), e.g. with
+/// `LANGFUSE_TRACING_ENABLED=false`, the installed provider records no span
+/// and has no span processor, and no runtime is needed.
+///
+/// Returns a guard that flushes and shuts the provider down when dropped.
+pub fn init_with(config: InitConfig) -> Result<TracingGuard> {
+    let builder = match config.exporter {
+        Some(builder) => builder,
+        None => ExporterBuilder::from_env()?,
+    };
+
+    let mut resource = Resource::builder().with_attributes(config.resource_attributes);
+    if let Some(name) = config.service_name {
+        resource = resource.with_service_name(name);
+    }
+    let provider = SdkTracerProvider::builder()
+        .with_sampler(builder.sampler()?)
+        .with_resource(resource.build());
+
+    if !builder.tracing_enabled() {
+        return Ok(install(provider.build(), config.shutdown_timeout));
+    }
+
+    let runtime = Handle::try_current().ok();
+    if runtime.is_none() && !builder.uses_blocking_client() {
+        return Err(Error::MissingRuntime);
+    }
+    let exporter = builder.build()?;
+    let batch_config = config.batch_config.unwrap_or_default();
+
+    let provider = match runtime.map(|handle| handle.runtime_flavor()) {
+        // A current-thread runtime cannot drive the processor while the guard
+        // blocks it on shutdown, so the processor gets a runtime of its own
+        Some(RuntimeFlavor::CurrentThread) => provider.with_span_processor(
+            span_processor_with_async_runtime::BatchSpanProcessor::builder(
+                exporter,
+                TokioCurrentThread,
+            )
+            .with_batch_config(batch_config)
+            .build(),
+        ),
+        Some(_) => provider.with_span_processor(
+            span_processor_with_async_runtime::BatchSpanProcessor::builder(exporter, Tokio)
+                .with_batch_config(batch_config)
+                .build(),
+        ),
+        None => provider.with_span_processor(
+            BatchSpanProcessor::builder(exporter)
+                .with_batch_config(batch_config)
+                .build(),
+        ),
+    }
+    .build();
+
+    Ok(install(provider, config.shutdown_timeout))
+}
+
+/// Installs `provider` as the global tracer provider and returns its guard.
+fn install(provider: SdkTracerProvider, shutdown_timeout: Duration) -> TracingGuard {
+    global::set_tracer_provider(provider.clone());
+
+    TracingGuard {
+        provider: Some(provider),
+        shutdown_timeout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockResponse, MockServer};
+    use crate::RetryPolicy;
+    use opentelemetry::trace::{Span as _, Tracer, TracerProvider as _};
+    use serial_test::serial;
+    use std::time::Instant;
+
+    fn config(host: &str) -> InitConfig {
+        InitConfig::new()
+            .with_exporter(
+                ExporterBuilder::new()
+                    .with_host(host)
+                    .with_basic_auth("pk-test", "sk-test"),
+            )
+            .with_service_name("init-test")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_guard_flushes_on_drop() {
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let guard = init_with(config(&server.url())).unwrap();
+
+        guard.provider().tracer("test").in_span("work", |_| {});
+        assert!(server.requests().is_empty());
+        drop(guard);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/api/public/otel/v1/traces");
+        let service = b"init-test";
+        assert!(requests[0]
+            .body
+            .windows(service.len())
+            .any(|w| w == service));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_guard_flushes_on_current_thread_runtime() {
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let guard = init_with(config(&server.url())).unwrap();
+
+        guard.provider().tracer("test").in_span("work", |_| {});
+        guard.shutdown().unwrap();
+
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_guard_gives_up_after_deadline() {
+        // Nothing listens on the discard port, so the export keeps retrying
+        let guard = init_with(
+            InitConfig::new()
+                .with_exporter(
+                    ExporterBuilder::new()
+                        .with_host("http://127.0.0.1:9")
+                        .with_basic_auth("pk-test", "sk-test")
+                        .with_retry_policy(
+                            RetryPolicy::new()
+                                .with_max_attempts(100)
+                                .with_initial_backoff(Duration::from_secs(1)),
+                        ),
+                )
+                .with_shutdown_timeout(Duration::from_millis(200)),
+        )
+        .unwrap();
+
+        guard.provider().tracer("test").in_span("work", |_| {});
+        let started = Instant::now();
+        let result = guard.shutdown();
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(matches!(
+            result,
+            Err(Error::Export(OTelSdkError::Timeout(_)))
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_init_without_runtime_requires_blocking_client() {
+        let result = init_with(config("http://127.0.0.1:9"));
+
+        assert!(matches!(result, Err(Error::MissingRuntime)));
+    }
+
+    #[test]
+    #[serial]
+    fn test_init_with_tracing_disabled() {
+        // Neither keys nor a runtime are needed
+        let guard = init_with(
+            InitConfig::new().with_exporter(ExporterBuilder::new().with_tracing_enabled(false)),
+        )
+        .unwrap();
+
+        let span = guard.provider().tracer("test").start("work");
+        assert!(!span.is_recording());
+        guard.shutdown().unwrap();
+    }
+
+    #[cfg(feature = "blocking-client")]
+    #[test]
+    #[serial]
+    fn test_init_without_runtime_with_blocking_client() {
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let guard = init_with(
+            InitConfig::new().with_exporter(
+                ExporterBuilder::new()
+                    .with_host(&server.url())
+                    .with_basic_auth("pk-test", "sk-test")
+                    .with_blocking_http_client(reqwest::blocking::Client::new()),
+            ),
+        )
+        .unwrap();
+
+        guard.provider().tracer("test").in_span("work", |_| {});
+        drop(guard);
+
+        assert_eq!(server.requests().len(), 1);
+    }
+}
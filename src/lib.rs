@@ -43,21 +43,67 @@
 //! # }
 //! ```
 //!
+//! [`init()`] wraps these steps in a single call and returns a guard that flushes
+//! and shuts down the provider when dropped.
+//!
 //! # Components
 //!
 //! ## Exporter
 //! - Configured OTLP/HTTP exporter for sending traces to Langfuse
 //! - Automatic authentication header setup
+//! - Credential providers consulted per export, for key rotation without restarts
+//! - Keys zeroed on drop, and a loggable configuration with a masked public key
 //! - Environment variable configuration support
 //! - Builder pattern for custom configuration
+//! - Retries with exponential backoff for transient failures
+//! - Durable on-disk spool for batches that could not be exported
+//! - Splitting of batches above the Langfuse request size limit
+//! - Circuit breaker that pauses exports after repeated authentication or endpoint failures
+//! - Self-telemetry metrics through the OpenTelemetry metrics API
+//! - Custom root certificates, mutual TLS and TLS backend selection
+//! - Connectivity and credential check before exporting
+//! - Region presets and a region allowlist for data residency
+//! - Sample rate and tracing switch shared with the other Langfuse SDKs
+//! - Alternative transport through the Langfuse ingestion API for older self-hosted versions
+//!
+//! ## Fan-out Exporter
+//! - Sends every batch to Langfuse and to additional OTLP backends
+//! - Isolates Langfuse from slow or failing secondary destinations
+//!
+//! ## File Exporter
+//! - Writes spans with their resource to rotating NDJSON files for offline environments
+//! - Uploads the files later through a regular exporter configuration
+//!
+//! ## Routing Exporter
+//! - Routes each span to one of several Langfuse projects by attribute
+//! - Default project for spans that match no route
+//!
+//! ## Tail Sampling
+//! - Span processor deciding on whole traces once their root span ends
+//! - Keep rules for errored, expensive or custom traces, and a sample ratio for the rest
+//! - Time and memory bounds on the buffered spans
 //!
 //! # Environment Variables
 //!
 //! This crate uses Langfuse-specific environment variables for configuration:
 //!
 //! - `LANGFUSE_HOST`: Base URL of your Langfuse instance (defaults to `https://cloud.langfuse.com`)
+//! - `LANGFUSE_REGION`: Region of your Langfuse instance, `eu`, `us`, `hipaa` or a self-hosted URL, used when `LANGFUSE_HOST` is not set (optional)
+//! - `LANGFUSE_ALLOWED_REGIONS`: Comma-separated regions the exporter may send to (optional)
 //! - `LANGFUSE_PUBLIC_KEY`: Your Langfuse public key (required)
 //! - `LANGFUSE_SECRET_KEY`: Your Langfuse secret key (required)
+//! - `LANGFUSE_PUBLIC_KEY_FILE` / `LANGFUSE_SECRET_KEY_FILE`: Paths of files holding the keys, re-read when they change (optional)
+//! - `LANGFUSE_COMPRESSION`: Compression of export requests, `gzip` or `zstd` (optional)
+//! - `LANGFUSE_CA_CERTIFICATE`: Path of a PEM bundle of trusted root certificates (optional)
+//! - `LANGFUSE_CLIENT_CERTIFICATE` / `LANGFUSE_CLIENT_KEY`: Paths of the PEM client certificate and key for mutual TLS (optional)
+//! - `LANGFUSE_TLS_MIN_VERSION`: Minimum TLS version, `1.2` or `1.3` (optional)
+//! - `LANGFUSE_SAMPLE_RATE`: Fraction of traces to record, from `0.0` to `1.0` (optional)
+//! - `LANGFUSE_TRACING_ENABLED`: `false` disables tracing, the keys are not required then (optional)
+//!
+//! The standard `OTEL_EXPORTER_OTLP_(TRACES_)ENDPOINT`, `_HEADERS`, `_TIMEOUT`
+//! and `_COMPRESSION` variables are honoured as well. Explicit builder calls
+//! take precedence over `LANGFUSE_*` variables, which take precedence over the
+//! `OTEL_*` ones.
 //!
 //! Example:
 //! ```bash
@@ -67,17 +113,71 @@
 //! ```
 //!
 //! Use `ExporterBuilder::from_env()` to create an exporter using these variables.
+//!
+//! To load the same settings from a configuration file instead, deserialize a
+//! [`LangfuseConfig`] and pass it to [`ExporterBuilder::from_config`].
+//!
+//! # Feature Flags
+//!
+//! - `blocking-client`: Enables a blocking HTTP client that needs no async runtime
+//! - `gzip-http`: Enables gzip compression of export requests
+//! - `native-tls`: Enables TLS through the platform's TLS library
+//! - `rustls-tls`: Enables TLS through rustls
+//! - `http-json`: Enables the OTLP/HTTP JSON protocol
+//! - `toml`: Enables loading TOML configuration files with `LangfuseConfig::from_file`
+//! - `zstd-http`: Enables zstd compression of export requests
 
 pub mod auth;
+mod batch;
+pub mod circuit_breaker;
+pub mod config;
 pub mod constants;
 pub mod context;
+pub mod credentials;
 pub mod endpoint;
 pub mod error;
 pub mod exporter;
+pub mod fanout;
+pub mod file;
+mod ingestion;
+pub mod init;
+mod metrics;
+mod otlp;
+pub mod region;
+pub mod retry;
+pub mod routing;
+pub mod sampling;
+pub mod secret;
+pub mod spool;
+pub mod tls;
+mod transport;
+pub mod verify;
+
+#[cfg(test)]
+mod test_util;
 
 // Re-export main types
 pub use auth::{build_auth_header, build_auth_header_from_env};
+pub use circuit_breaker::{CircuitBreaker, CircuitOpenReason, CircuitState};
+pub use config::{BatchSettings, LangfuseConfig, TlsSettings};
 pub use context::LangfuseContext;
-pub use endpoint::{build_otlp_endpoint, build_otlp_endpoint_from_env};
+pub use credentials::{CredentialProvider, Credentials};
+#[allow(deprecated)]
+pub use endpoint::build_otlp_endpoint;
+pub use endpoint::{
+    build_ingestion_endpoint, build_otlp_endpoint_from_env, try_build_otlp_endpoint,
+};
 pub use error::{Error, Result};
-pub use exporter::{exporter, ExporterBuilder};
+pub use exporter::{exporter, ExporterBuilder, LangfuseExporter, Protocol, Transport};
+pub use fanout::{FanoutExporter, FanoutExporterBuilder};
+pub use file::{FileExporter, FileExporterBuilder, UploadSummary};
+pub use init::{init, init_with, InitConfig, TracingGuard};
+pub use opentelemetry_otlp::Compression;
+pub use region::LangfuseRegion;
+pub use retry::RetryPolicy;
+pub use routing::{RoutingExporter, RoutingExporterBuilder};
+pub use sampling::{KeepRule, TailSamplingProcessor, TailSamplingProcessorBuilder};
+pub use secret::SecretString;
+pub use spool::SpoolConfig;
+pub use tls::{TlsBackend, TlsVersion};
+pub use verify::{Verification, VerifyError};
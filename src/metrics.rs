@@ -0,0 +1,159 @@
+//! Self-telemetry metrics of the Langfuse exporter.
+//!
+//! The exporter records the following instruments through the OpenTelemetry
+//! metrics API, under the `opentelemetry-langfuse` instrumentation scope:
+//!
+//! - `langfuse.exporter.spans.exported`: spans accepted by Langfuse
+//! - `langfuse.exporter.spans.failed`: spans that could not be exported
+//! - `langfuse.exporter.batches.retried`: retries of export requests
+//! - `langfuse.exporter.bytes.sent`: request body bytes sent, including retries
+//! - `langfuse.exporter.export.duration`: latency of export requests, in seconds
+//! - `langfuse.exporter.batch.size`: body size of export requests, in bytes
+//!
+//! The span counters and both histograms carry an `http.status_class`
+//! attribute (`2xx`, `4xx`, `5xx`, ...), or `error` when no response was
+//! received.
+//!
+//! Spans whose export failed but that were spooled for replay count as failed,
+//! and again as exported once their replay is accepted.
+
+use http::StatusCode;
+use opentelemetry::metrics::{Counter, Histogram, Meter, MeterProvider};
+use opentelemetry::{InstrumentationScope, KeyValue};
+use std::time::Duration;
+
+/// Name of the instrumentation scope of the exporter metrics.
+const SCOPE_NAME: &str = "opentelemetry-langfuse";
+
+/// Attribute carrying the HTTP status class of an export request.
+const STATUS_CLASS: &str = "http.status_class";
+
+/// Returns the meter of the exporter metrics from `provider`.
+pub(crate) fn meter<P: MeterProvider + ?Sized>(provider: &P) -> Meter {
+    provider.meter_with_scope(
+        InstrumentationScope::builder(SCOPE_NAME)
+            .with_version(env!("CARGO_PKG_VERSION"))
+            .build(),
+    )
+}
+
+/// Instruments recorded by the exporter and its transport.
+#[derive(Debug, Clone)]
+pub(crate) struct ExporterMetrics {
+    spans_exported: Counter<u64>,
+    spans_failed: Counter<u64>,
+    batches_retried: Counter<u64>,
+    bytes_sent: Counter<u64>,
+    export_duration: Histogram<f64>,
+    batch_size: Histogram<u64>,
+}
+
+impl ExporterMetrics {
+    /// Creates the instruments on `meter`.
+    pub(crate) fn new(meter: &Meter) -> Self {
+        Self {
+            spans_exported: meter
+                .u64_counter("langfuse.exporter.spans.exported")
+                .with_description("Number of spans accepted by Langfuse")
+                .with_unit("{span}")
+                .build(),
+            spans_failed: meter
+                .u64_counter("langfuse.exporter.spans.failed")
+                .with_description("Number of spans that could not be exported")
+                .with_unit("{span}")
+                .build(),
+            batches_retried: meter
+                .u64_counter("langfuse.exporter.batches.retried")
+                .with_description("Number of retried export requests")
+                .with_unit("{retry}")
+                .build(),
+            bytes_sent: meter
+                .u64_counter("langfuse.exporter.bytes.sent")
+                .with_description("Request body bytes sent to Langfuse, including retries")
+                .with_unit("By")
+                .build(),
+            export_duration: meter
+                .f64_histogram("langfuse.exporter.export.duration")
+                .with_description("Duration of export requests, including retries")
+                .with_unit("s")
+                .with_boundaries(vec![
+                    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+                ])
+                .build(),
+            batch_size: meter
+                .u64_histogram("langfuse.exporter.batch.size")
+                .with_description("Body size of export requests")
+                .with_unit("By")
+                .with_boundaries(vec![
+                    1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0,
+                ])
+                .build(),
+        }
+    }
+
+    /// Records the outcome of exporting `spans` spans.
+    ///
+    /// `status` is the status of the last response, `None` if none was received.
+    pub(crate) fn record_export(&self, spans: usize, success: bool, status: Option<StatusCode>) {
+        let counter = if success {
+            &self.spans_exported
+        } else {
+            &self.spans_failed
+        };
+        counter.add(
+            spans as u64,
+            &[KeyValue::new(STATUS_CLASS, status_class(status))],
+        );
+    }
+
+    /// Records a retry of an export request.
+    pub(crate) fn record_retry(&self) {
+        self.batches_retried.add(1, &[]);
+    }
+
+    /// Records a request body sent over the wire.
+    pub(crate) fn record_bytes_sent(&self, bytes: usize) {
+        self.bytes_sent.add(bytes as u64, &[]);
+    }
+
+    /// Records a completed export request.
+    ///
+    /// `status` is `None` if no response was received.
+    pub(crate) fn record_request(
+        &self,
+        duration: Duration,
+        bytes: usize,
+        status: Option<StatusCode>,
+    ) {
+        let attributes = [KeyValue::new(STATUS_CLASS, status_class(status))];
+        self.export_duration
+            .record(duration.as_secs_f64(), &attributes);
+        self.batch_size.record(bytes as u64, &attributes);
+    }
+}
+
+/// Returns the status class label of a response status.
+fn status_class(status: Option<StatusCode>) -> &'static str {
+    match status.map(|status| status.as_u16() / 100) {
+        Some(1) => "1xx",
+        Some(2) => "2xx",
+        Some(3) => "3xx",
+        Some(4) => "4xx",
+        Some(5) => "5xx",
+        _ => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_class() {
+        assert_eq!(status_class(Some(StatusCode::OK)), "2xx");
+        assert_eq!(status_class(Some(StatusCode::NO_CONTENT)), "2xx");
+        assert_eq!(status_class(Some(StatusCode::UNAUTHORIZED)), "4xx");
+        assert_eq!(status_class(Some(StatusCode::BAD_GATEWAY)), "5xx");
+        assert_eq!(status_class(None), "error");
+    }
+}
@@ -0,0 +1,212 @@
+//! Transport sending spans to the Langfuse OTLP endpoint.
+//!
+//! Requests are encoded the same way as by the `opentelemetry-otlp` HTTP
+//! exporter, but the protocol and compression are the ones resolved by the
+//! [`ExporterBuilder`](crate::ExporterBuilder). Unlike the stock exporter, it
+//! never reads the `OTEL_EXPORTER_OTLP_*` variables itself, so a compression
+//! of `none` is honoured whatever these variables say.
+
+use crate::transport::LangfuseHttpClient;
+use crate::{Compression, Protocol};
+use bytes::Bytes;
+use http::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use http::{Method, Request};
+use opentelemetry_http::HttpClient;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use prost::Message;
+
+/// Exports spans as OTLP/HTTP requests.
+#[derive(Debug)]
+pub(crate) struct OtlpExporter {
+    client: LangfuseHttpClient,
+    endpoint: String,
+    protocol: Protocol,
+    compression: Option<Compression>,
+    resource: ResourceAttributesWithSchema,
+}
+
+impl OtlpExporter {
+    /// Creates an exporter posting to the OTLP `endpoint`.
+    pub(crate) fn new(
+        client: LangfuseHttpClient,
+        endpoint: String,
+        protocol: Protocol,
+        compression: Option<Compression>,
+    ) -> Self {
+        Self {
+            client,
+            endpoint,
+            protocol,
+            compression,
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+
+    /// Sets the resource sent with every request.
+    pub(crate) fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+    }
+
+    /// Sends `batch` in a single export request.
+    pub(crate) async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let request = ExportTraceServiceRequest {
+            resource_spans: group_spans_by_resource_and_scope(batch, &self.resource),
+        };
+        let (body, content_type) = encode(&request, self.protocol)?;
+        let (body, content_encoding) = compress(body, self.compression)?;
+
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.endpoint)
+            .header(CONTENT_TYPE, content_type);
+        if let Some(content_encoding) = content_encoding {
+            request = request.header(CONTENT_ENCODING, content_encoding);
+        }
+        let request = request
+            .body(Bytes::from(body))
+            .map_err(|err| OTelSdkError::InternalFailure(err.to_string()))?;
+
+        let response = self.client.send_bytes(request).await.map_err(|err| {
+            OTelSdkError::InternalFailure(format!("OTLP export request failed: {}", err))
+        })?;
+        if !response.status().is_success() {
+            return Err(OTelSdkError::InternalFailure(format!(
+                "OTLP export request failed with status {}: {}",
+                response.status(),
+                String::from_utf8_lossy(response.body())
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Encodes an export request, returning it with its content type.
+fn encode(
+    request: &ExportTraceServiceRequest,
+    protocol: Protocol,
+) -> Result<(Vec<u8>, &'static str), OTelSdkError> {
+    match protocol {
+        #[cfg(feature = "http-json")]
+        Protocol::HttpJson => serde_json::to_vec(request)
+            .map(|body| (body, "application/json"))
+            .map_err(|err| OTelSdkError::InternalFailure(err.to_string())),
+        _ => Ok((request.encode_to_vec(), "application/x-protobuf")),
+    }
+}
+
+/// Compresses a request body, returning it with its content encoding.
+///
+/// The builder rejects compressions whose cargo feature is not enabled, so
+/// these never get here.
+fn compress(
+    body: Vec<u8>,
+    compression: Option<Compression>,
+) -> Result<(Vec<u8>, Option<&'static str>), OTelSdkError> {
+    match compression {
+        None => Ok((body, None)),
+        #[cfg(feature = "gzip-http")]
+        Some(Compression::Gzip) => {
+            use flate2::write::GzEncoder;
+            use std::io::Write;
+
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(&body)
+                .and_then(|()| encoder.finish())
+                .map(|body| (body, Some("gzip")))
+                .map_err(|err| OTelSdkError::InternalFailure(err.to_string()))
+        }
+        #[cfg(feature = "zstd-http")]
+        Some(Compression::Zstd) => zstd::bulk::compress(&body, 0)
+            .map(|body| (body, Some("zstd")))
+            .map_err(|err| OTelSdkError::InternalFailure(err.to_string())),
+        #[allow(unreachable_patterns)]
+        Some(compression) => Err(OTelSdkError::InternalFailure(format!(
+            "{} compression is not enabled",
+            compression
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{span_data, MockResponse, MockServer};
+
+    fn exporter(server: &MockServer, compression: Option<Compression>) -> OtlpExporter {
+        OtlpExporter::new(
+            LangfuseHttpClient::new(reqwest::Client::new()),
+            format!("{}/api/public/otel/v1/traces", server.url()),
+            Protocol::HttpBinary,
+            compression,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_posts_protobuf_request() {
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let mut exporter = exporter(&server, None);
+        exporter.set_resource(
+            &Resource::builder_empty()
+                .with_service_name("checkout")
+                .build(),
+        );
+
+        exporter
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/public/otel/v1/traces");
+        assert_eq!(
+            requests[0].header("content-type"),
+            Some("application/x-protobuf")
+        );
+        assert_eq!(requests[0].header("content-encoding"), None);
+        let request = ExportTraceServiceRequest::decode(requests[0].body.as_slice()).unwrap();
+        let resource = request.resource_spans[0].resource.as_ref().unwrap();
+        assert_eq!(resource.attributes[0].key, "service.name");
+        assert_eq!(request.resource_spans[0].scope_spans[0].spans.len(), 1);
+    }
+
+    #[cfg(feature = "gzip-http")]
+    #[tokio::test]
+    async fn test_compresses_request() {
+        use std::io::Read;
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        exporter(&server, Some(Compression::Gzip))
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("content-encoding"), Some("gzip"));
+        let mut body = Vec::new();
+        flate2::read::GzDecoder::new(requests[0].body.as_slice())
+            .read_to_end(&mut body)
+            .unwrap();
+        assert!(ExportTraceServiceRequest::decode(body.as_slice()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_request_fails_the_export() {
+        let server = MockServer::start(vec![MockResponse::status(400).with_body("invalid span")]);
+
+        let result = exporter(&server, None)
+            .export(vec![span_data(1, 1, 0, vec![])])
+            .await;
+
+        let Err(OTelSdkError::InternalFailure(message)) = result else {
+            panic!("expected the export to fail");
+        };
+        assert!(message.contains("400"));
+        assert!(message.contains("invalid span"));
+    }
+}
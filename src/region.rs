@@ -0,0 +1,279 @@
+//! Langfuse cloud regions and data-residency checks.
+//!
+//! [`LangfuseRegion`] selects the host of a Langfuse cloud region, or of a
+//! self-hosted instance, with
+//! [`ExporterBuilder::with_region`](crate::ExporterBuilder::with_region) or the
+//! `LANGFUSE_REGION` environment variable.
+//!
+//! [`ExporterBuilder::with_allowed_regions`](crate::ExporterBuilder::with_allowed_regions)
+//! and `LANGFUSE_ALLOWED_REGIONS` restrict the regions an exporter may send
+//! to: [`build`](crate::ExporterBuilder::build) fails with
+//! [`Error::RegionNotAllowed`] when the final endpoint, however it was
+//! configured, belongs to another region.
+//!
+//! # Example
+//!
+//! ```
+//! use opentelemetry_langfuse::{Error, ExporterBuilder, LangfuseRegion};
+//!
+//! // EU traces must never reach another region
+//! let result = ExporterBuilder::new()
+//!     .with_host("https://us.cloud.langfuse.com")
+//!     .with_basic_auth("pk-lf-...", "sk-lf-...")
+//!     .with_allowed_regions([LangfuseRegion::Eu])
+//!     .build();
+//!
+//! assert!(matches!(result, Err(Error::RegionNotAllowed { .. })));
+//! ```
+
+use crate::constants::{
+    DEFAULT_LANGFUSE_HOST, ENV_LANGFUSE_ALLOWED_REGIONS, ENV_LANGFUSE_REGION, HIPAA_LANGFUSE_HOST,
+    US_LANGFUSE_HOST,
+};
+use crate::{endpoint, Error, Result};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+/// Region of a Langfuse instance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LangfuseRegion {
+    /// Langfuse cloud in the EU, <https://cloud.langfuse.com>.
+    Eu,
+    /// Langfuse cloud in the US, <https://us.cloud.langfuse.com>.
+    Us,
+    /// HIPAA-compliant Langfuse cloud in the US, <https://hipaa.cloud.langfuse.com>.
+    Hipaa,
+    /// A self-hosted instance, identified by its base URL.
+    SelfHosted(String),
+}
+
+impl LangfuseRegion {
+    /// Returns the base URL of the region.
+    pub fn host(&self) -> &str {
+        match self {
+            LangfuseRegion::Eu => DEFAULT_LANGFUSE_HOST,
+            LangfuseRegion::Us => US_LANGFUSE_HOST,
+            LangfuseRegion::Hipaa => HIPAA_LANGFUSE_HOST,
+            LangfuseRegion::SelfHosted(url) => url,
+        }
+    }
+
+    /// Returns the region an OTLP or ingestion endpoint, or a base URL,
+    /// belongs to.
+    ///
+    /// URLs that are not a Langfuse cloud host are [`LangfuseRegion::SelfHosted`].
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The endpoint or base URL
+    pub fn of_endpoint(endpoint: &str) -> Self {
+        let host = normalize(&endpoint::host_url(endpoint));
+        [
+            LangfuseRegion::Eu,
+            LangfuseRegion::Us,
+            LangfuseRegion::Hipaa,
+        ]
+        .into_iter()
+        .find(|region| region.host() == host)
+        .unwrap_or(LangfuseRegion::SelfHosted(host))
+    }
+
+    /// Returns whether an endpoint belongs to this region.
+    pub(crate) fn contains(&self, endpoint: &str) -> bool {
+        match (self, Self::of_endpoint(endpoint)) {
+            (LangfuseRegion::SelfHosted(allowed), LangfuseRegion::SelfHosted(host)) => {
+                normalize(allowed) == host
+            }
+            (allowed, region) => *allowed == region,
+        }
+    }
+}
+
+impl fmt::Display for LangfuseRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LangfuseRegion::Eu => f.write_str("eu"),
+            LangfuseRegion::Us => f.write_str("us"),
+            LangfuseRegion::Hipaa => f.write_str("hipaa"),
+            LangfuseRegion::SelfHosted(url) => f.write_str(url),
+        }
+    }
+}
+
+/// Parses `eu`, `us`, `hipaa` (case-insensitive), or the `http://` or
+/// `https://` base URL of a self-hosted instance.
+impl FromStr for LangfuseRegion {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        match value.to_ascii_lowercase().as_str() {
+            "eu" => Ok(LangfuseRegion::Eu),
+            "us" => Ok(LangfuseRegion::Us),
+            "hipaa" => Ok(LangfuseRegion::Hipaa),
+            _ => endpoint::validate_base_url(value)
+                .map(|url| LangfuseRegion::SelfHosted(normalize(&url)))
+                .map_err(|_| Error::InvalidRegion(value.to_string())),
+        }
+    }
+}
+
+/// Checks that `endpoint` belongs to one of the `allowed` regions.
+///
+/// An empty allowlist allows every region.
+pub(crate) fn check_allowed(endpoint: &str, allowed: &[LangfuseRegion]) -> Result<()> {
+    if allowed.is_empty() || allowed.iter().any(|region| region.contains(endpoint)) {
+        return Ok(());
+    }
+    Err(Error::RegionNotAllowed {
+        endpoint: endpoint.to_string(),
+        region: LangfuseRegion::of_endpoint(endpoint).to_string(),
+    })
+}
+
+/// Reads the region from `LANGFUSE_REGION`, if set.
+pub(crate) fn region_from_env() -> Result<Option<LangfuseRegion>> {
+    let Ok(value) = env::var(ENV_LANGFUSE_REGION) else {
+        return Ok(None);
+    };
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| Error::InvalidEnvironmentVariable {
+            name: ENV_LANGFUSE_REGION,
+            value,
+        })
+}
+
+/// Reads the comma-separated allowlist from `LANGFUSE_ALLOWED_REGIONS`.
+pub(crate) fn allowed_regions_from_env() -> Result<Vec<LangfuseRegion>> {
+    let Ok(value) = env::var(ENV_LANGFUSE_ALLOWED_REGIONS) else {
+        return Ok(Vec::new());
+    };
+    value
+        .split(',')
+        .filter(|region| !region.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<_>>()
+        .map_err(|_| Error::InvalidEnvironmentVariable {
+            name: ENV_LANGFUSE_ALLOWED_REGIONS,
+            value,
+        })
+}
+
+/// Lowercases the scheme and host of a base URL and strips trailing slashes.
+fn normalize(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    format!(
+        "{}://{}{}",
+        scheme.to_ascii_lowercase(),
+        authority.to_ascii_lowercase(),
+        path
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!("EU".parse::<LangfuseRegion>().unwrap(), LangfuseRegion::Eu);
+        assert_eq!(
+            " us ".parse::<LangfuseRegion>().unwrap(),
+            LangfuseRegion::Us
+        );
+        assert_eq!(
+            "hipaa".parse::<LangfuseRegion>().unwrap(),
+            LangfuseRegion::Hipaa
+        );
+        assert_eq!(
+            "https://Langfuse.Internal/"
+                .parse::<LangfuseRegion>()
+                .unwrap(),
+            LangfuseRegion::SelfHosted("https://langfuse.internal".into())
+        );
+        assert!(matches!(
+            "asia".parse::<LangfuseRegion>(),
+            Err(Error::InvalidRegion(_))
+        ));
+    }
+
+    #[test]
+    fn test_region_of_endpoint() {
+        assert_eq!(
+            LangfuseRegion::of_endpoint("https://cloud.langfuse.com/api/public/otel/v1/traces"),
+            LangfuseRegion::Eu
+        );
+        assert_eq!(
+            LangfuseRegion::of_endpoint("https://US.cloud.langfuse.com/"),
+            LangfuseRegion::Us
+        );
+        assert_eq!(
+            LangfuseRegion::of_endpoint("https://langfuse.internal/api/public/ingestion"),
+            LangfuseRegion::SelfHosted("https://langfuse.internal".into())
+        );
+    }
+
+    #[test]
+    fn test_check_allowed() {
+        let eu = "https://cloud.langfuse.com/api/public/otel/v1/traces";
+        let us = "https://us.cloud.langfuse.com/api/public/otel/v1/traces";
+        let internal = "https://langfuse.internal/api/public/otel/v1/traces";
+
+        assert!(check_allowed(us, &[]).is_ok());
+        assert!(check_allowed(eu, &[LangfuseRegion::Eu]).is_ok());
+        assert!(check_allowed(
+            internal,
+            &[LangfuseRegion::SelfHosted(
+                "https://langfuse.internal/".into()
+            )]
+        )
+        .is_ok());
+
+        match check_allowed(us, &[LangfuseRegion::Eu]) {
+            Err(Error::RegionNotAllowed { endpoint, region }) => {
+                assert_eq!(endpoint, us);
+                assert_eq!(region, "us");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(check_allowed(internal, &[LangfuseRegion::Eu]).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_allowed_regions_from_env() {
+        env::set_var(
+            ENV_LANGFUSE_ALLOWED_REGIONS,
+            "eu, https://langfuse.internal",
+        );
+        assert_eq!(
+            allowed_regions_from_env().unwrap(),
+            vec![
+                LangfuseRegion::Eu,
+                LangfuseRegion::SelfHosted("https://langfuse.internal".into())
+            ]
+        );
+
+        env::set_var(ENV_LANGFUSE_ALLOWED_REGIONS, "eu,mars");
+        assert!(matches!(
+            allowed_regions_from_env(),
+            Err(Error::InvalidEnvironmentVariable {
+                name: ENV_LANGFUSE_ALLOWED_REGIONS,
+                ..
+            })
+        ));
+
+        env::remove_var(ENV_LANGFUSE_ALLOWED_REGIONS);
+    }
+}
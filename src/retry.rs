@@ -0,0 +1,282 @@
+//! Retry policy for Langfuse exports.
+//!
+//! A [`RetryPolicy`] controls how the exporter retries requests that failed
+//! with a transient error, such as a rate limit, a gateway error or a dropped
+//! connection. Requests are retried with exponential backoff and jitter until
+//! either the maximum number of attempts or the overall deadline is reached.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{ExporterBuilder, RetryPolicy};
+//! use std::time::Duration;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = ExporterBuilder::from_env()?
+//!     .with_retry_policy(
+//!         RetryPolicy::new()
+//!             .with_max_attempts(5)
+//!             .with_initial_backoff(Duration::from_millis(200))
+//!             .with_max_elapsed_time(Duration::from_secs(30)),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use http::{header::RETRY_AFTER, HeaderMap, StatusCode};
+use std::time::{Duration, SystemTime};
+
+/// Retry policy with exponential backoff, jitter, an attempt cap and an overall deadline.
+///
+/// The following responses are retried:
+/// - `408 Request Timeout`
+/// - `429 Too Many Requests` (honouring `Retry-After`)
+/// - `500 Internal Server Error`, `502 Bad Gateway`, `504 Gateway Timeout`
+/// - `503 Service Unavailable` (honouring `Retry-After`)
+/// - connection errors, resets and timeouts
+///
+/// All other responses, including `400 Bad Request`, `401 Unauthorized` and
+/// `403 Forbidden`, are never retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_elapsed_time: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a retry policy with the default settings.
+    ///
+    /// The defaults are 5 attempts, an initial backoff of 500ms doubling up to
+    /// 30s, 20% jitter and an overall deadline of 60s.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_elapsed_time: Duration::from_secs(60),
+        }
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    ///
+    /// A value of `1` disables retries. Values below `1` are treated as `1`.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the upper bound for the computed delay between two attempts.
+    ///
+    /// A `Retry-After` value sent by the server is not capped by this limit,
+    /// only by the overall deadline.
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the factor by which the delay grows after each attempt.
+    ///
+    /// Values below `1.0` are treated as `1.0`.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Sets the jitter factor applied to each delay.
+    ///
+    /// A factor of `0.2` randomizes each delay within ±20% of its computed value.
+    /// The value is clamped to `0.0..=1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the overall deadline for a request, including all retries and delays.
+    ///
+    /// No retry is started if its delay would end past this deadline.
+    pub fn with_max_elapsed_time(mut self, max_elapsed_time: Duration) -> Self {
+        self.max_elapsed_time = max_elapsed_time;
+        self
+    }
+
+    /// Returns the maximum number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the overall deadline for a request.
+    pub fn max_elapsed_time(&self) -> Duration {
+        self.max_elapsed_time
+    }
+
+    /// Computes the delay before the next attempt, after `attempt` attempts have failed.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let factor = 1.0 + self.jitter * (2.0 * rand::random::<f64>() - 1.0);
+        // Out of range for a Duration, e.g. with a `max_backoff` of `Duration::MAX`
+        Duration::try_from_secs_f64((capped * factor).max(0.0)).unwrap_or(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns whether a response with the given status is worth retrying.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Returns whether a transport error is worth retrying.
+pub(crate) fn is_retryable_error(err: &reqwest::Error) -> bool {
+    !(err.is_builder() || err.is_redirect() || err.is_status())
+}
+
+/// Reads the `Retry-After` delay from a `429` or `503` response.
+///
+/// Both the delay-seconds and the HTTP-date form are supported.
+pub(crate) fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    #[test]
+    fn test_backoff_grows_exponentially_and_is_capped() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500))
+            .with_jitter(0.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(50), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_backoff_with_huge_max_backoff() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_secs(1))
+            .with_max_backoff(Duration::MAX)
+            .with_multiplier(10.0)
+            .with_jitter(0.0);
+
+        assert_eq!(policy.backoff(1_000), Duration::MAX);
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(1000))
+            .with_jitter(0.5);
+
+        for _ in 0..100 {
+            let delay = policy.backoff(1);
+            assert!(delay >= Duration::from_millis(500));
+            assert!(delay <= Duration::from_millis(1500));
+        }
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(is_retryable_status(StatusCode::from_u16(status).unwrap()));
+        }
+        for status in [200, 400, 401, 403, 404, 413] {
+            assert!(!is_retryable_status(StatusCode::from_u16(status).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+
+        assert_eq!(
+            retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            retry_after(StatusCode::SERVICE_UNAVAILABLE, &headers),
+            Some(Duration::from_secs(7))
+        );
+        // Only honoured on 429 and 503
+        assert_eq!(retry_after(StatusCode::BAD_GATEWAY, &headers), None);
+    }
+
+    #[test]
+    fn test_retry_after_http_date() {
+        let mut headers = HeaderMap::new();
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(120));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+
+        let delay = retry_after(StatusCode::TOO_MANY_REQUESTS, &headers).unwrap();
+        assert!(delay > Duration::from_secs(100));
+        assert!(delay <= Duration::from_secs(120));
+
+        // Dates in the past mean "retry now"
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(
+            retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_builder_clamps_values() {
+        let policy = RetryPolicy::new()
+            .with_max_attempts(0)
+            .with_multiplier(0.5)
+            .with_jitter(3.0);
+
+        assert_eq!(policy.max_attempts(), 1);
+        assert_eq!(policy.multiplier, 1.0);
+        assert_eq!(policy.jitter, 1.0);
+    }
+}
@@ -0,0 +1,367 @@
+//! Routing exporter that sends spans to one of several Langfuse projects.
+//!
+//! A [`RoutingExporter`] picks the destination project for each trace from an
+//! attribute, such as `langfuse.project` or `service.name`. The attribute is
+//! looked up on the spans of the trace first and on the resource second, so
+//! setting it on the root span routes the child spans exported in the same
+//! batch along with it. Traces without a matching route go to the default
+//! project.
+//!
+//! Spans are routed batch by batch, and child spans usually end, and may be
+//! exported, before their root. When a trace can span several batches, set
+//! the attribute on every span of the trace, or on the resource.
+//!
+//! Projects are exported to concurrently, so a slow project does not hold up
+//! the others.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{ExporterBuilder, RoutingExporter};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = RoutingExporter::builder("langfuse.project", ExporterBuilder::from_env()?)
+//!     .with_route(
+//!         "search",
+//!         ExporterBuilder::new()
+//!             .with_host("https://cloud.langfuse.com")
+//!             .with_basic_auth("pk-lf-search", "sk-lf-search"),
+//!     )
+//!     .with_route(
+//!         "billing",
+//!         ExporterBuilder::new()
+//!             .with_host("https://cloud.langfuse.com")
+//!             .with_basic_auth("pk-lf-billing", "sk-lf-billing"),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{ExporterBuilder, LangfuseExporter, Result};
+use futures_util::future::join_all;
+use opentelemetry::trace::{SpanId, TraceId};
+use opentelemetry::Key;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter;
+use std::time::{Duration, Instant};
+
+/// Exporter that routes each trace to one of several Langfuse projects.
+#[derive(Debug)]
+pub struct RoutingExporter {
+    attribute: Key,
+    routes: Vec<(String, LangfuseExporter)>,
+    default: LangfuseExporter,
+    /// Value of the routing attribute on the resource, if any.
+    resource_value: Option<String>,
+}
+
+impl RoutingExporter {
+    /// Creates a builder routing on `attribute`, with `default` receiving all
+    /// spans that match no route.
+    pub fn builder(attribute: impl Into<Key>, default: ExporterBuilder) -> RoutingExporterBuilder {
+        RoutingExporterBuilder::new(attribute, default)
+    }
+
+    /// Returns the value of the routing attribute on a span, if any.
+    fn span_value<'a>(&self, span: &'a SpanData) -> Option<Cow<'a, str>> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key == self.attribute)
+            .map(|kv| kv.value.as_str())
+    }
+
+    /// Returns the index of the route for a value of the routing attribute,
+    /// or `None` for the default project.
+    fn route(&self, value: Option<&str>) -> Option<usize> {
+        let value = value.or(self.resource_value.as_deref())?;
+        self.routes.iter().position(|(route, _)| route == value)
+    }
+
+    /// Splits a batch into the spans of each route and of the default project.
+    ///
+    /// Every span of a trace follows the value found on its root span, or
+    /// else on any other span of the trace in the batch.
+    fn split(&self, batch: Vec<SpanData>) -> (Vec<Vec<SpanData>>, Vec<SpanData>) {
+        let mut values: HashMap<TraceId, String> = HashMap::new();
+        for span in &batch {
+            if let Some(value) = self.span_value(span) {
+                let is_root = span.parent_span_id == SpanId::INVALID || span.parent_span_is_remote;
+                let trace_id = span.span_context.trace_id();
+                if is_root || !values.contains_key(&trace_id) {
+                    values.insert(trace_id, value.into_owned());
+                }
+            }
+        }
+
+        let mut routed: Vec<Vec<SpanData>> = vec![Vec::new(); self.routes.len()];
+        let mut default = Vec::new();
+        for span in batch {
+            let value = values.get(&span.span_context.trace_id());
+            match self.route(value.map(String::as_str)) {
+                Some(index) => routed[index].push(span),
+                None => default.push(span),
+            }
+        }
+        (routed, default)
+    }
+}
+
+impl opentelemetry_sdk::trace::SpanExporter for RoutingExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let (routed, default) = self.split(batch);
+
+        // Export every project concurrently, even if one of them fails
+        let exports = self
+            .routes
+            .iter()
+            .map(|(_, exporter)| exporter)
+            .zip(routed)
+            .chain(iter::once((&self.default, default)))
+            .filter(|(_, spans)| !spans.is_empty())
+            .map(|(exporter, spans)| exporter.export(spans));
+        join_all(exports).await.into_iter().collect()
+    }
+
+    /// Shuts every project down, all within `timeout`.
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        let deadline = Instant::now() + timeout;
+        let mut result = Ok(());
+        for exporter in self
+            .routes
+            .iter_mut()
+            .map(|(_, exporter)| exporter)
+            .chain(iter::once(&mut self.default))
+        {
+            result = result.and(
+                exporter.shutdown_with_timeout(deadline.saturating_duration_since(Instant::now())),
+            );
+        }
+        result
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource_value = resource
+            .get(&self.attribute)
+            .map(|value| value.as_str().into_owned());
+
+        for (_, exporter) in &mut self.routes {
+            exporter.set_resource(resource);
+        }
+        self.default.set_resource(resource);
+    }
+}
+
+/// Builder for a [`RoutingExporter`].
+pub struct RoutingExporterBuilder {
+    attribute: Key,
+    routes: Vec<(String, ExporterBuilder)>,
+    default: ExporterBuilder,
+}
+
+impl RoutingExporterBuilder {
+    /// Creates a builder routing on `attribute`, with `default` receiving all
+    /// spans that match no route.
+    pub fn new(attribute: impl Into<Key>, default: ExporterBuilder) -> Self {
+        Self {
+            attribute: attribute.into(),
+            routes: Vec::new(),
+            default,
+        }
+    }
+
+    /// Adds a route for spans whose routing attribute equals `value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The attribute value selecting this route
+    /// * `exporter` - The exporter configuration of the project
+    pub fn with_route(mut self, value: impl Into<String>, exporter: ExporterBuilder) -> Self {
+        self.routes.push((value.into(), exporter));
+        self
+    }
+
+    /// Builds the routing exporter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the project exporters cannot be built.
+    pub fn build(self) -> Result<RoutingExporter> {
+        Ok(RoutingExporter {
+            attribute: self.attribute,
+            routes: self
+                .routes
+                .into_iter()
+                .map(|(value, builder)| Ok((value, builder.build()?)))
+                .collect::<Result<_>>()?,
+            default: self.default.build()?,
+            resource_value: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{span_data, MockResponse, MockServer};
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::trace::SpanExporter as _;
+    use std::net::TcpListener;
+
+    fn project(server: &MockServer) -> ExporterBuilder {
+        ExporterBuilder::new()
+            .with_host(&server.url())
+            .with_basic_auth("pk-test", "sk-test")
+    }
+
+    fn contains(body: &[u8], needle: &str) -> bool {
+        body.windows(needle.len()).any(|w| w == needle.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn test_routes_spans_by_span_attribute() {
+        let search = MockServer::start(vec![MockResponse::status(200)]);
+        let billing = MockServer::start(vec![MockResponse::status(200)]);
+        let fallback = MockServer::start(vec![MockResponse::status(200)]);
+
+        let exporter = RoutingExporter::builder("langfuse.project", project(&fallback))
+            .with_route("search", project(&search))
+            .with_route("billing", project(&billing))
+            .build()
+            .unwrap();
+
+        exporter
+            .export(vec![
+                span_data(1, 1, 0, vec![KeyValue::new("langfuse.project", "search")]),
+                span_data(2, 2, 0, vec![KeyValue::new("langfuse.project", "billing")]),
+                span_data(3, 3, 0, vec![KeyValue::new("langfuse.project", "unknown")]),
+                span_data(4, 4, 0, vec![]),
+            ])
+            .await
+            .unwrap();
+
+        let search = search.requests();
+        assert_eq!(search.len(), 1);
+        assert!(contains(&search[0].body, "span-1"));
+        assert!(!contains(&search[0].body, "span-2"));
+
+        let billing = billing.requests();
+        assert_eq!(billing.len(), 1);
+        assert!(contains(&billing[0].body, "span-2"));
+
+        let fallback = fallback.requests();
+        assert_eq!(fallback.len(), 1);
+        assert!(contains(&fallback[0].body, "span-3"));
+        assert!(contains(&fallback[0].body, "span-4"));
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_resource_attribute() {
+        let search = MockServer::start(vec![MockResponse::status(200)]);
+        let fallback = MockServer::start(vec![MockResponse::status(200)]);
+
+        let mut exporter = RoutingExporter::builder("service.name", project(&fallback))
+            .with_route("search-api", project(&search))
+            .build()
+            .unwrap();
+        exporter.set_resource(
+            &Resource::builder_empty()
+                .with_attribute(KeyValue::new("service.name", "search-api"))
+                .build(),
+        );
+
+        exporter
+            .export(vec![
+                span_data(1, 1, 0, vec![]),
+                // The span attribute wins over the resource attribute
+                span_data(2, 2, 0, vec![KeyValue::new("service.name", "other")]),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(search.requests().len(), 1);
+        assert!(contains(&search.requests()[0].body, "span-1"));
+        assert_eq!(fallback.requests().len(), 1);
+        assert!(contains(&fallback.requests()[0].body, "span-2"));
+    }
+
+    #[tokio::test]
+    async fn test_routes_whole_trace_by_root_span() {
+        let search = MockServer::start(vec![MockResponse::status(200)]);
+        let fallback = MockServer::start(vec![MockResponse::status(200)]);
+
+        let exporter = RoutingExporter::builder("langfuse.project", project(&fallback))
+            .with_route("search", project(&search))
+            .build()
+            .unwrap();
+
+        exporter
+            .export(vec![
+                // Children end before their root
+                span_data(1, 11, 10, vec![]),
+                span_data(1, 12, 11, vec![KeyValue::new("langfuse.project", "other")]),
+                span_data(1, 10, 0, vec![KeyValue::new("langfuse.project", "search")]),
+                span_data(2, 20, 0, vec![]),
+            ])
+            .await
+            .unwrap();
+
+        let search = search.requests();
+        assert_eq!(search.len(), 1);
+        for span in ["span-10", "span-11", "span-12"] {
+            assert!(contains(&search[0].body, span));
+        }
+        assert!(!contains(&search[0].body, "span-20"));
+        assert_eq!(fallback.requests().len(), 1);
+        assert!(contains(&fallback.requests()[0].body, "span-20"));
+    }
+
+    #[tokio::test]
+    async fn test_slow_project_does_not_hold_up_others() {
+        let fallback = MockServer::start(vec![MockResponse::status(200)]);
+        // Accepts connections but never answers
+        let stalled = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stalled = ExporterBuilder::new()
+            .with_host(&format!("http://{}", stalled.local_addr().unwrap()))
+            .with_basic_auth("pk-test", "sk-test");
+
+        let exporter = RoutingExporter::builder("langfuse.project", project(&fallback))
+            .with_route("stalled", stalled)
+            .build()
+            .unwrap();
+
+        let export = exporter.export(vec![
+            span_data(1, 1, 0, vec![KeyValue::new("langfuse.project", "stalled")]),
+            span_data(2, 2, 0, vec![]),
+        ]);
+        let result = tokio::time::timeout(Duration::from_millis(500), export).await;
+
+        assert!(result.is_err());
+        assert_eq!(fallback.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_project_does_not_block_others() {
+        let failing = MockServer::start(vec![MockResponse::status(500)]);
+        let fallback = MockServer::start(vec![MockResponse::status(200)]);
+
+        let exporter = RoutingExporter::builder("langfuse.project", project(&fallback))
+            .with_route("failing", project(&failing))
+            .build()
+            .unwrap();
+
+        let result = exporter
+            .export(vec![
+                span_data(1, 1, 0, vec![KeyValue::new("langfuse.project", "failing")]),
+                span_data(2, 2, 0, vec![]),
+            ])
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(fallback.requests().len(), 1);
+    }
+}
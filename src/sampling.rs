@@ -0,0 +1,813 @@
+//! Tail-based sampling that keeps every interesting trace.
+//!
+//! A [`TailSamplingProcessor`] sits in front of another span processor, e.g.
+//! a batch span processor exporting to Langfuse. It buffers the spans of each
+//! trace until its root span ends, then evaluates [`KeepRule`]s over the whole
+//! trace: traces matching any rule are always forwarded, all others only at
+//! the sample ratio. The ratio is applied on the trace ID, so the same traces
+//! are kept by every service using the same ratio.
+//!
+//! The buffer is bounded in time and memory. A trace whose root span has not
+//! ended within the [decision wait](TailSamplingProcessorBuilder::with_decision_wait),
+//! e.g. because the root is in another process, and the oldest traces once
+//! the buffer holds too many [spans](TailSamplingProcessorBuilder::with_max_buffered_spans)
+//! or [bytes](TailSamplingProcessorBuilder::with_max_buffered_bytes), are
+//! decided on the spans buffered so far. Expired traces are decided by a
+//! background thread, so they are not held back until another span ends.
+//! Spans ending after the decision on their trace follow it.
+//!
+//! The tracer provider must record every span, e.g. with the default
+//! `AlwaysOn` sampler and without `LANGFUSE_SAMPLE_RATE`, for the rules to see
+//! the whole trace.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::sampling::{KeepRule, TailSamplingProcessor};
+//! use opentelemetry_langfuse::ExporterBuilder;
+//! use opentelemetry_sdk::trace::{BatchSpanProcessor, SdkTracerProvider};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = ExporterBuilder::from_env()?.build()?;
+//!
+//! // 10% of normal traffic, but every errored or expensive trace
+//! let processor = TailSamplingProcessor::builder(BatchSpanProcessor::builder(exporter).build(), 0.1)
+//!     .with_rule(KeepRule::error_status())
+//!     .with_rule(KeepRule::error_level())
+//!     .with_rule(KeepRule::min_total_cost(0.05))
+//!     .with_rule(KeepRule::min_total_tokens(10_000))
+//!     .build();
+//!
+//! let provider = SdkTracerProvider::builder()
+//!     .with_span_processor(processor)
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::batch::{span_size, Encoding};
+use opentelemetry::trace::{SpanId, Status, TraceId};
+use opentelemetry::{Context, Value};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time a trace is buffered while waiting for its root span.
+const DEFAULT_DECISION_WAIT: Duration = Duration::from_secs(30);
+
+/// Default maximum number of buffered spans, over all traces.
+const DEFAULT_MAX_BUFFERED_SPANS: usize = 10_000;
+
+/// Default maximum size of the buffered spans, over all traces (64 MiB).
+const DEFAULT_MAX_BUFFERED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Number of decisions remembered for spans ending after their trace was decided.
+const MAX_DECISIONS: usize = 10_000;
+
+const OBSERVATION_LEVEL: &str = "langfuse.observation.level";
+const OBSERVATION_USAGE_DETAILS: &str = "langfuse.observation.usage_details";
+const OBSERVATION_COST_DETAILS: &str = "langfuse.observation.cost_details";
+const GEN_AI_INPUT_TOKENS: &str = "gen_ai.usage.input_tokens";
+const GEN_AI_OUTPUT_TOKENS: &str = "gen_ai.usage.output_tokens";
+const GEN_AI_COST: &str = "gen_ai.usage.cost";
+
+/// Predicate of a [`KeepRule`].
+type Predicate = Arc<dyn Fn(&[SpanData]) -> bool + Send + Sync>;
+
+/// A condition over the spans of a trace that keeps the trace.
+#[derive(Clone)]
+pub struct KeepRule {
+    name: String,
+    matches: Predicate,
+}
+
+impl KeepRule {
+    /// Creates a rule from a predicate over the spans of a trace.
+    ///
+    /// The predicate runs while the buffer is locked and should be cheap.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the rule, shown in the `Debug` output
+    /// * `matches` - Returns whether the trace must be kept
+    pub fn new(
+        name: impl Into<String>,
+        matches: impl Fn(&[SpanData]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            matches: Arc::new(matches),
+        }
+    }
+
+    /// Keeps traces with a span whose status is `Error`.
+    pub fn error_status() -> Self {
+        Self::new("error_status", |spans| {
+            spans
+                .iter()
+                .any(|span| matches!(span.status, Status::Error { .. }))
+        })
+    }
+
+    /// Keeps traces with an observation whose `langfuse.observation.level` is `ERROR`.
+    pub fn error_level() -> Self {
+        Self::new("error_level", |spans| {
+            spans.iter().any(|span| {
+                attribute(span, OBSERVATION_LEVEL)
+                    .is_some_and(|level| level.as_str().eq_ignore_ascii_case("ERROR"))
+            })
+        })
+    }
+
+    /// Keeps traces whose total cost reaches `threshold`.
+    ///
+    /// The cost of an observation is the `total` of its
+    /// `langfuse.observation.cost_details`, or the sum of its entries without
+    /// a total, falling back to `gen_ai.usage.cost`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The minimum total cost, in the currency of the costs
+    pub fn min_total_cost(threshold: f64) -> Self {
+        Self::new(format!("min_total_cost({})", threshold), move |spans| {
+            let cost: f64 = spans
+                .iter()
+                .filter_map(|span| {
+                    details_total(span, OBSERVATION_COST_DETAILS)
+                        .or_else(|| attribute(span, GEN_AI_COST).and_then(number))
+                })
+                .sum();
+            cost >= threshold
+        })
+    }
+
+    /// Keeps traces whose total token count reaches `threshold`.
+    ///
+    /// The token count of an observation is the `total` of its
+    /// `langfuse.observation.usage_details`, or the sum of its entries without
+    /// a total, falling back to `gen_ai.usage.input_tokens` plus
+    /// `gen_ai.usage.output_tokens`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The minimum total number of tokens
+    pub fn min_total_tokens(threshold: u64) -> Self {
+        Self::new(format!("min_total_tokens({})", threshold), move |spans| {
+            let tokens: f64 = spans
+                .iter()
+                .filter_map(|span| {
+                    details_total(span, OBSERVATION_USAGE_DETAILS).or_else(|| {
+                        let input = attribute(span, GEN_AI_INPUT_TOKENS).and_then(number);
+                        let output = attribute(span, GEN_AI_OUTPUT_TOKENS).and_then(number);
+                        input
+                            .or(output)
+                            .map(|_| input.unwrap_or(0.0) + output.unwrap_or(0.0))
+                    })
+                })
+                .sum();
+            tokens >= threshold as f64
+        })
+    }
+
+    /// Returns whether the trace made of `spans` must be kept.
+    fn matches(&self, spans: &[SpanData]) -> bool {
+        (self.matches)(spans)
+    }
+}
+
+impl fmt::Debug for KeepRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeepRule").field(&self.name).finish()
+    }
+}
+
+/// Spans of a trace waiting for a decision.
+#[derive(Debug)]
+struct PendingTrace {
+    spans: Vec<SpanData>,
+    /// Encoded size of the spans, in bytes.
+    size: usize,
+    /// When the first span of the trace ended.
+    first_seen: Instant,
+}
+
+/// Buffer of a [`TailSamplingProcessor`].
+#[derive(Debug, Default)]
+struct Buffer {
+    traces: HashMap<TraceId, PendingTrace>,
+    /// Pending traces in the order their first span ended.
+    order: VecDeque<(TraceId, Instant)>,
+    buffered_spans: usize,
+    buffered_bytes: usize,
+    /// Recent decisions, for spans ending after their trace was decided.
+    decisions: HashMap<TraceId, bool>,
+    decision_order: VecDeque<TraceId>,
+}
+
+impl Buffer {
+    /// Remembers the decision on a trace, forgetting the oldest one if needed.
+    fn record(&mut self, trace_id: TraceId, keep: bool) {
+        if self.decisions.insert(trace_id, keep).is_none() {
+            self.decision_order.push_back(trace_id);
+        }
+        if self.decision_order.len() > MAX_DECISIONS {
+            if let Some(oldest) = self.decision_order.pop_front() {
+                self.decisions.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// State of a [`TailSamplingProcessor`], shared with its sweeper thread.
+struct Shared<P> {
+    inner: P,
+    rules: Vec<KeepRule>,
+    sample_ratio: f64,
+    decision_wait: Duration,
+    max_buffered_spans: usize,
+    max_buffered_bytes: usize,
+    buffer: Mutex<Buffer>,
+}
+
+impl<P: SpanProcessor> Shared<P> {
+    fn buffer(&self) -> MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Decides on a pending trace, returning its spans if it is kept.
+    fn decide(&self, buffer: &mut Buffer, trace_id: TraceId) -> Vec<SpanData> {
+        let Some(trace) = buffer.traces.remove(&trace_id) else {
+            return Vec::new();
+        };
+        buffer.buffered_spans -= trace.spans.len();
+        buffer.buffered_bytes -= trace.size;
+
+        let keep = self.rules.iter().any(|rule| rule.matches(&trace.spans))
+            || ratio_sampled(trace_id, self.sample_ratio);
+        buffer.record(trace_id, keep);
+        if keep {
+            trace.spans
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Decides on the traces waiting too long, then on the oldest ones while
+    /// the buffer is full, returning the spans of the kept ones.
+    fn expire(&self, buffer: &mut Buffer, now: Instant) -> Vec<SpanData> {
+        let mut kept = Vec::new();
+        while let Some(&(oldest, first_seen)) = buffer.order.front() {
+            let expired = now.duration_since(first_seen) >= self.decision_wait;
+            if !expired
+                && buffer.buffered_spans <= self.max_buffered_spans
+                && buffer.buffered_bytes <= self.max_buffered_bytes
+            {
+                break;
+            }
+            buffer.order.pop_front();
+            // Skip traces that were decided already, or buffered again since
+            if buffer
+                .traces
+                .get(&oldest)
+                .is_some_and(|trace| trace.first_seen == first_seen)
+            {
+                kept.extend(self.decide(buffer, oldest));
+            }
+        }
+        kept
+    }
+
+    /// Decides on the expired traces and forwards the kept ones.
+    ///
+    /// Returns when the next pending trace expires, if any.
+    fn sweep(&self) -> Option<Instant> {
+        let (kept, next) = {
+            let mut buffer = self.buffer();
+            let kept = self.expire(&mut buffer, Instant::now());
+            let next = buffer
+                .order
+                .front()
+                .map(|&(_, first_seen)| first_seen + self.decision_wait);
+            (kept, next)
+        };
+        for span in kept {
+            self.inner.on_end(span);
+        }
+        next
+    }
+
+    /// Decides on every pending trace and forwards the kept ones.
+    fn decide_all(&self) {
+        let kept: Vec<SpanData> = {
+            let mut buffer = self.buffer();
+            let pending: Vec<TraceId> = buffer.traces.keys().copied().collect();
+            buffer.order.clear();
+            pending
+                .into_iter()
+                .flat_map(|trace_id| self.decide(&mut buffer, trace_id))
+                .collect()
+        };
+        for span in kept {
+            self.inner.on_end(span);
+        }
+    }
+}
+
+/// Starts the thread deciding on traces whose decision wait expired, even if
+/// no span ends anymore.
+///
+/// The thread is woken up by the returned sender when a trace is buffered,
+/// and stops once the sender or the processor is dropped. Returns `None` if
+/// the thread cannot be started, in which case the wait is only checked
+/// when a span ends.
+fn start_sweeper<P: SpanProcessor + 'static>(shared: Weak<Shared<P>>) -> Option<Sender<()>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .name("langfuse-tail-sampling".to_string())
+        .spawn(move || {
+            // Started when a trace is buffered, so look at the buffer right away
+            let mut next = Some(Instant::now());
+            loop {
+                let received = match next {
+                    Some(deadline) => {
+                        receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                if received == Err(RecvTimeoutError::Disconnected) {
+                    return;
+                }
+                let Some(shared) = shared.upgrade() else {
+                    return;
+                };
+                next = shared.sweep();
+            }
+        })
+        .ok()?;
+    Some(sender)
+}
+
+/// Span processor that buffers each trace and forwards it to another
+/// processor if it matches a [`KeepRule`] or is sampled at the sample ratio.
+pub struct TailSamplingProcessor<P> {
+    shared: Arc<Shared<P>>,
+    /// Wakes up the sweeper thread, started when the first trace is buffered.
+    sweeper: Mutex<Option<Sender<()>>>,
+}
+
+impl<P: SpanProcessor + 'static> TailSamplingProcessor<P> {
+    /// Creates a builder forwarding kept traces to `inner`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The processor receiving the spans of kept traces
+    /// * `sample_ratio` - The fraction of traces matching no rule that are
+    ///   kept, from `0.0` to `1.0`
+    pub fn builder(inner: P, sample_ratio: f64) -> TailSamplingProcessorBuilder<P> {
+        TailSamplingProcessorBuilder::new(inner, sample_ratio)
+    }
+
+    fn sweeper(&self) -> MutexGuard<'_, Option<Sender<()>>> {
+        self.sweeper.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Wakes up the sweeper thread, starting it if needed.
+    fn wake_sweeper(&self) {
+        let mut sweeper = self.sweeper();
+        if sweeper
+            .as_ref()
+            .is_none_or(|sender| sender.send(()).is_err())
+        {
+            *sweeper = start_sweeper(Arc::downgrade(&self.shared));
+        }
+    }
+}
+
+impl<P: SpanProcessor + 'static> SpanProcessor for TailSamplingProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.shared.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        if !span.span_context.is_sampled() {
+            return;
+        }
+        let shared = &self.shared;
+        let trace_id = span.span_context.trace_id();
+        // A span with a remote parent is the root of the local part of the trace
+        let is_root = span.parent_span_id == SpanId::INVALID || span.parent_span_is_remote;
+        let now = Instant::now();
+
+        let mut kept = Vec::new();
+        let new_trace = {
+            let mut buffer = shared.buffer();
+            let new_trace = match buffer.decisions.get(&trace_id) {
+                Some(true) => {
+                    kept.push(span);
+                    false
+                }
+                Some(false) => false,
+                None => {
+                    let size = span_size(&span, Encoding::Protobuf);
+                    let trace = buffer
+                        .traces
+                        .entry(trace_id)
+                        .or_insert_with(|| PendingTrace {
+                            spans: Vec::new(),
+                            size: 0,
+                            first_seen: now,
+                        });
+                    trace.spans.push(span);
+                    trace.size += size;
+                    let new_trace = trace.spans.len() == 1;
+                    if new_trace {
+                        buffer.order.push_back((trace_id, now));
+                    }
+                    buffer.buffered_spans += 1;
+                    buffer.buffered_bytes += size;
+
+                    if is_root {
+                        kept.extend(shared.decide(&mut buffer, trace_id));
+                    }
+                    new_trace
+                }
+            };
+
+            kept.extend(shared.expire(&mut buffer, now));
+            new_trace && buffer.traces.contains_key(&trace_id)
+        };
+
+        // The sweeper thread sleeps until the oldest trace expires, or
+        // indefinitely while the buffer is empty
+        if new_trace {
+            self.wake_sweeper();
+        }
+        for span in kept {
+            shared.inner.on_end(span);
+        }
+    }
+
+    /// Decides on all buffered traces, whether their root span ended or not,
+    /// and flushes the inner processor.
+    fn force_flush(&self) -> OTelSdkResult {
+        self.shared.decide_all();
+        self.shared.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        // Dropping the sender stops the sweeper thread
+        self.sweeper().take();
+        self.shared.decide_all();
+        self.shared.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        // The resource is set when the tracer provider is built, before any
+        // span ends and thus before the sweeper thread shares the state
+        if let Some(shared) = Arc::get_mut(&mut self.shared) {
+            shared.inner.set_resource(resource);
+        }
+    }
+}
+
+impl<P: fmt::Debug> fmt::Debug for TailSamplingProcessor<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TailSamplingProcessor")
+            .field("inner", &self.shared.inner)
+            .field("rules", &self.shared.rules)
+            .field("sample_ratio", &self.shared.sample_ratio)
+            .field("decision_wait", &self.shared.decision_wait)
+            .field("max_buffered_spans", &self.shared.max_buffered_spans)
+            .field("max_buffered_bytes", &self.shared.max_buffered_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for a [`TailSamplingProcessor`].
+#[derive(Debug)]
+pub struct TailSamplingProcessorBuilder<P> {
+    inner: P,
+    rules: Vec<KeepRule>,
+    sample_ratio: f64,
+    decision_wait: Duration,
+    max_buffered_spans: usize,
+    max_buffered_bytes: usize,
+}
+
+impl<P: SpanProcessor + 'static> TailSamplingProcessorBuilder<P> {
+    /// Creates a builder forwarding kept traces to `inner`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The processor receiving the spans of kept traces
+    /// * `sample_ratio` - The fraction of traces matching no rule that are
+    ///   kept, from `0.0` to `1.0`
+    pub fn new(inner: P, sample_ratio: f64) -> Self {
+        Self {
+            inner,
+            rules: Vec::new(),
+            sample_ratio,
+            decision_wait: DEFAULT_DECISION_WAIT,
+            max_buffered_spans: DEFAULT_MAX_BUFFERED_SPANS,
+            max_buffered_bytes: DEFAULT_MAX_BUFFERED_BYTES,
+        }
+    }
+
+    /// Adds a rule keeping the traces it matches, regardless of the sample ratio.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - The keep rule
+    pub fn with_rule(mut self, rule: KeepRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Sets how long a trace is buffered while waiting for its root span.
+    ///
+    /// Defaults to 30 seconds. A background thread decides on the traces
+    /// whose wait expired, even if no other span ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `wait` - The maximum time between the end of the first span of a
+    ///   trace and the decision on it
+    pub fn with_decision_wait(mut self, wait: Duration) -> Self {
+        self.decision_wait = wait;
+        self
+    }
+
+    /// Sets the maximum number of buffered spans, over all traces.
+    ///
+    /// Defaults to 10,000. When the buffer is full, the oldest traces are
+    /// decided early.
+    ///
+    /// # Arguments
+    ///
+    /// * `spans` - The maximum number of buffered spans
+    pub fn with_max_buffered_spans(mut self, spans: usize) -> Self {
+        self.max_buffered_spans = spans;
+        self
+    }
+
+    /// Sets the maximum size of the buffered spans, over all traces.
+    ///
+    /// Spans are measured by their OTLP protobuf encoding, so large inputs
+    /// and outputs count fully. Defaults to 64 MiB. When the buffer is full,
+    /// the oldest traces are decided early.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The maximum size of the buffered spans, in bytes
+    pub fn with_max_buffered_bytes(mut self, bytes: usize) -> Self {
+        self.max_buffered_bytes = bytes;
+        self
+    }
+
+    /// Builds the tail sampling processor.
+    pub fn build(self) -> TailSamplingProcessor<P> {
+        TailSamplingProcessor {
+            shared: Arc::new(Shared {
+                inner: self.inner,
+                rules: self.rules,
+                sample_ratio: self.sample_ratio,
+                decision_wait: self.decision_wait,
+                max_buffered_spans: self.max_buffered_spans,
+                max_buffered_bytes: self.max_buffered_bytes,
+                buffer: Mutex::new(Buffer::default()),
+            }),
+            sweeper: Mutex::new(None),
+        }
+    }
+}
+
+/// Returns whether a trace is sampled at `ratio`, based on its trace ID.
+///
+/// Uses the same computation as the `TraceIdRatioBased` sampler of the SDK.
+pub(crate) fn ratio_sampled(trace_id: TraceId, ratio: f64) -> bool {
+    if ratio >= 1.0 {
+        return true;
+    }
+    let mut low = [0; 8];
+    low.copy_from_slice(&trace_id.to_bytes()[8..]);
+    let low = u64::from_be_bytes(low) >> 1;
+    let upper_bound = (ratio.max(0.0) * (1u64 << 63) as f64) as u64;
+    low < upper_bound
+}
+
+/// Returns the value of an attribute of a span.
+fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+/// Returns a numeric attribute value, parsing strings.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::I64(value) => Some(*value as f64),
+        Value::F64(value) => Some(*value),
+        Value::String(value) => value.as_str().trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns the total of a JSON details attribute, such as
+/// `{"input": 10, "output": 20, "total": 30}`.
+///
+/// Without a `total` entry, the numeric entries are summed.
+fn details_total(span: &SpanData, key: &str) -> Option<f64> {
+    let details: HashMap<String, JsonValue> =
+        serde_json::from_str(attribute(span, key)?.as_str().as_ref()).ok()?;
+    if let Some(total) = details.get("total").and_then(JsonValue::as_f64) {
+        return Some(total);
+    }
+    Some(details.values().filter_map(JsonValue::as_f64).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::span_data;
+    use opentelemetry::KeyValue;
+
+    /// Processor recording the names of the spans it receives.
+    #[derive(Debug, Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn names(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl SpanProcessor for Recorder {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span.name.into_owned());
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    fn errored(mut span: SpanData) -> SpanData {
+        span.status = Status::error("boom");
+        span
+    }
+
+    #[test]
+    fn test_keeps_whole_trace_matching_a_rule() {
+        let recorder = Recorder::default();
+        let processor = TailSamplingProcessor::builder(recorder.clone(), 0.0)
+            .with_rule(KeepRule::error_status())
+            .build();
+
+        // Errored child, kept with its root once the root ends
+        processor.on_end(errored(span_data(1, 11, 10, vec![])));
+        assert!(recorder.names().is_empty());
+        processor.on_end(span_data(1, 10, 0, vec![]));
+
+        // Trace without errors, dropped at a ratio of 0
+        processor.on_end(span_data(2, 21, 20, vec![]));
+        processor.on_end(span_data(2, 20, 0, vec![]));
+
+        assert_eq!(recorder.names(), vec!["span-11", "span-10"]);
+
+        // A late span follows the decision on its trace
+        processor.on_end(span_data(1, 12, 10, vec![]));
+        processor.on_end(span_data(2, 22, 20, vec![]));
+        assert_eq!(recorder.names(), vec!["span-11", "span-10", "span-12"]);
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let trace = |attributes: Vec<KeyValue>| vec![span_data(1, 1, 0, attributes)];
+
+        let rule = KeepRule::error_level();
+        assert!(rule.matches(&trace(vec![KeyValue::new(OBSERVATION_LEVEL, "ERROR")])));
+        assert!(!rule.matches(&trace(vec![KeyValue::new(OBSERVATION_LEVEL, "WARNING")])));
+
+        let rule = KeepRule::min_total_cost(0.05);
+        assert!(rule.matches(&trace(vec![KeyValue::new(
+            OBSERVATION_COST_DETAILS,
+            r#"{"input": 0.01, "output": 0.02, "total": 0.06}"#,
+        )])));
+        assert!(!rule.matches(&trace(vec![KeyValue::new(GEN_AI_COST, 0.01)])));
+
+        // Summed over the spans of the trace
+        let rule = KeepRule::min_total_tokens(1000);
+        let spans = vec![
+            span_data(1, 1, 0, vec![KeyValue::new(GEN_AI_INPUT_TOKENS, 400)]),
+            span_data(
+                1,
+                2,
+                1,
+                vec![KeyValue::new(
+                    OBSERVATION_USAGE_DETAILS,
+                    r#"{"input": 300, "output": 300}"#,
+                )],
+            ),
+        ];
+        assert!(rule.matches(&spans));
+        assert!(!rule.matches(&spans[..1]));
+    }
+
+    #[test]
+    fn test_buffer_is_bounded() {
+        let recorder = Recorder::default();
+        let processor = TailSamplingProcessor::builder(recorder.clone(), 1.0)
+            .with_max_buffered_spans(2)
+            .build();
+
+        // Roots of other processes never end here
+        processor.on_end(span_data(1, 11, 10, vec![]));
+        processor.on_end(span_data(2, 21, 20, vec![]));
+        assert!(recorder.names().is_empty());
+        processor.on_end(span_data(3, 31, 30, vec![]));
+        assert_eq!(recorder.names(), vec!["span-11"]);
+
+        let recorder = Recorder::default();
+        let processor = TailSamplingProcessor::builder(recorder.clone(), 1.0)
+            .with_decision_wait(Duration::ZERO)
+            .build();
+        processor.on_end(span_data(1, 11, 10, vec![]));
+        assert_eq!(recorder.names(), vec!["span-11"]);
+    }
+
+    #[test]
+    fn test_buffer_is_bounded_in_bytes() {
+        let recorder = Recorder::default();
+        let large = |trace, id, parent| {
+            span_data(
+                trace,
+                id,
+                parent,
+                vec![KeyValue::new("input", "x".repeat(1000))],
+            )
+        };
+        let processor = TailSamplingProcessor::builder(recorder.clone(), 1.0)
+            .with_max_buffered_bytes(2500)
+            .build();
+
+        processor.on_end(large(1, 11, 10));
+        processor.on_end(large(2, 21, 20));
+        assert!(recorder.names().is_empty());
+        processor.on_end(large(3, 31, 30));
+        assert_eq!(recorder.names(), vec!["span-11"]);
+    }
+
+    #[test]
+    fn test_decision_wait_expires_without_span_ends() {
+        let recorder = Recorder::default();
+        let processor = TailSamplingProcessor::builder(recorder.clone(), 1.0)
+            .with_decision_wait(Duration::from_millis(50))
+            .build();
+
+        // The root is in another process, and no other span ends
+        processor.on_end(span_data(1, 11, 10, vec![]));
+        assert!(recorder.names().is_empty());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while recorder.names().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(recorder.names(), vec!["span-11"]);
+    }
+
+    #[test]
+    fn test_flush_decides_pending_traces() {
+        let recorder = Recorder::default();
+        let processor = TailSamplingProcessor::builder(recorder.clone(), 0.0)
+            .with_rule(KeepRule::error_status())
+            .build();
+
+        processor.on_end(errored(span_data(1, 11, 10, vec![])));
+        processor.on_end(span_data(2, 21, 20, vec![]));
+        processor.force_flush().unwrap();
+
+        assert_eq!(recorder.names(), vec!["span-11"]);
+    }
+
+    #[test]
+    fn test_ratio_sampling_uses_trace_id() {
+        assert!(ratio_sampled(TraceId::from(u128::MAX), 1.0));
+        assert!(!ratio_sampled(TraceId::from(1), 0.0));
+        assert!(ratio_sampled(TraceId::from(1), 0.5));
+        assert!(!ratio_sampled(TraceId::from(u128::MAX), 0.5));
+    }
+}
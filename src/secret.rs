@@ -0,0 +1,70 @@
+//! Secret values that are wiped from memory and never printed.
+
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// A secret string, such as a Langfuse secret key or an Authorization header.
+///
+/// The memory of the string is zeroed when it is dropped, and its `Debug`
+/// output is `"<redacted>"`, so secrets do not end up in logs. Use
+/// [`expose_secret`](Self::expose_secret) to access the value.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    /// Wraps a secret value.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret value
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(Zeroizing::new(secret.into()))
+    }
+
+    /// Returns the secret value.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"<redacted>\"")
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let secret = SecretString::new("sk-lf-secret");
+
+        assert_eq!(format!("{:?}", secret), "\"<redacted>\"");
+        assert_eq!(
+            format!("{:?}", Some(secret.clone())),
+            "Some(\"<redacted>\")"
+        );
+        assert_eq!(secret.expose_secret(), "sk-lf-secret");
+    }
+}
@@ -0,0 +1,475 @@
+//! Durable on-disk spool for export requests that could not be delivered.
+//!
+//! When a spool is configured, batches that still fail after all retries are
+//! appended to a bounded, append-only segment log instead of being dropped.
+//! Spooled batches are replayed in order before the next export goes out, so
+//! they are delivered as soon as Langfuse is reachable again, including after
+//! a process restart.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_langfuse::{ExporterBuilder, RetryPolicy, SpoolConfig};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = ExporterBuilder::from_env()?
+//!     .with_retry_policy(RetryPolicy::new())
+//!     .with_spool(SpoolConfig::new("/var/lib/my-service/langfuse-spool"))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File extension of spool segments.
+const SEGMENT_EXTENSION: &str = "seg";
+
+/// Configuration for the on-disk spool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpoolConfig {
+    directory: PathBuf,
+    max_size_bytes: u64,
+    segment_size_bytes: u64,
+}
+
+impl SpoolConfig {
+    /// Creates a spool configuration storing segments in `directory`.
+    ///
+    /// The directory is created if it does not exist. By default the spool is
+    /// capped at 64 MiB, split into segments of 4 MiB.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_size_bytes: 64 * 1024 * 1024,
+            segment_size_bytes: 4 * 1024 * 1024,
+        }
+    }
+
+    /// Sets the maximum total size of the spool.
+    ///
+    /// When the spool grows past this size, the oldest segments are discarded.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size_bytes = bytes;
+        self
+    }
+
+    /// Sets the size after which a new segment is started.
+    pub fn with_segment_size(mut self, bytes: u64) -> Self {
+        self.segment_size_bytes = bytes.max(1);
+        self
+    }
+
+    /// Returns the spool directory.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
+
+/// A spooled export request body.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SpooledRequest {
+    pub(crate) content_type: Option<String>,
+    pub(crate) content_encoding: Option<String>,
+    /// Number of spans in the body, for the exporter metrics.
+    pub(crate) spans: u32,
+    pub(crate) body: Bytes,
+}
+
+impl SpooledRequest {
+    fn encode(&self) -> Vec<u8> {
+        let content_type = self.content_type.as_deref().unwrap_or_default().as_bytes();
+        let content_encoding = self
+            .content_encoding
+            .as_deref()
+            .unwrap_or_default()
+            .as_bytes();
+
+        let len = 4 + content_type.len() + 4 + content_encoding.len() + 4 + self.body.len();
+        let mut record = Vec::with_capacity(4 + len);
+        record.extend_from_slice(&(len as u32).to_be_bytes());
+        for field in [content_type, content_encoding] {
+            record.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            record.extend_from_slice(field);
+        }
+        record.extend_from_slice(&self.spans.to_be_bytes());
+        record.extend_from_slice(&self.body);
+        record
+    }
+
+    fn decode(mut data: &[u8]) -> Option<Self> {
+        let mut field = || -> Option<Option<String>> {
+            let len = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+            let value = String::from_utf8(data.get(4..4 + len)?.to_vec()).ok()?;
+            data = &data[4 + len..];
+            Some(Some(value).filter(|v| !v.is_empty()))
+        };
+        let content_type = field()?;
+        let content_encoding = field()?;
+        let spans = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+
+        Some(Self {
+            content_type,
+            content_encoding,
+            spans,
+            body: Bytes::copy_from_slice(&data[4..]),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Segment {
+    sequence: u64,
+    size: u64,
+}
+
+#[derive(Debug)]
+struct SpoolState {
+    segments: VecDeque<Segment>,
+    /// Sequence of the segment currently open for appends, if any.
+    active: Option<u64>,
+    /// Sequence of the segment currently being replayed, if any.
+    replaying: Option<u64>,
+    next_sequence: u64,
+}
+
+/// Bounded append-only segment log of spooled requests.
+#[derive(Debug)]
+pub(crate) struct Spool {
+    config: SpoolConfig,
+    state: Mutex<SpoolState>,
+}
+
+impl Spool {
+    /// Opens the spool, picking up segments left behind by a previous process.
+    pub(crate) fn open(config: SpoolConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&config.directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXTENSION) {
+                continue;
+            }
+            let Some(sequence) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            segments.push(Segment {
+                sequence,
+                size: fs::metadata(&path)?.len(),
+            });
+        }
+        segments.sort_by_key(|s| s.sequence);
+
+        let next_sequence = segments.last().map_or(0, |s| s.sequence + 1);
+        Ok(Self {
+            config,
+            state: Mutex::new(SpoolState {
+                segments: segments.into(),
+                active: None,
+                replaying: None,
+                next_sequence,
+            }),
+        })
+    }
+
+    /// Returns whether there are spooled requests waiting for replay.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().segments.is_empty()
+    }
+
+    /// Appends a request to the spool, discarding the oldest segments if the
+    /// spool grows past its size limit.
+    pub(crate) fn push(&self, request: &SpooledRequest) -> io::Result<()> {
+        let record = request.encode();
+        if record.len() as u64 > self.config.max_size_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "request is larger than the spool size limit",
+            ));
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let active = state
+            .active
+            .filter(|seq| {
+                state.segments.back().is_some_and(|s| {
+                    s.sequence == *seq
+                        && s.size + record.len() as u64 <= self.config.segment_size_bytes
+                })
+            })
+            .unwrap_or_else(|| {
+                let sequence = state.next_sequence;
+                state.next_sequence += 1;
+                state.segments.push_back(Segment { sequence, size: 0 });
+                sequence
+            });
+        state.active = Some(active);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.segment_path(active))?;
+        file.write_all(&record)?;
+        file.sync_data()?;
+
+        if let Some(segment) = state.segments.back_mut() {
+            segment.size += record.len() as u64;
+        }
+
+        // Enforce the size cap by discarding the oldest segments, except the
+        // newest one and the one being replayed
+        while state.segments.iter().map(|s| s.size).sum::<u64>() > self.config.max_size_bytes {
+            let replaying = state.replaying;
+            let Some(index) = state
+                .segments
+                .iter()
+                .take(state.segments.len() - 1)
+                .position(|s| Some(s.sequence) != replaying)
+            else {
+                break;
+            };
+            if let Some(oldest) = state.segments.remove(index) {
+                remove_file_if_exists(&self.segment_path(oldest.sequence))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes the oldest segment for replay.
+    ///
+    /// The segment is sealed, so requests spooled during replay go to a new
+    /// segment, and it is not discarded by the size cap until
+    /// [`complete`](Self::complete) or [`end_replay`](Self::end_replay).
+    /// Returns the segment sequence and its requests in order.
+    pub(crate) fn oldest(&self) -> io::Result<Option<(u64, Vec<SpooledRequest>)>> {
+        let mut state = self.state.lock().unwrap();
+        let Some(sequence) = state.segments.front().map(|s| s.sequence) else {
+            return Ok(None);
+        };
+        if state.active == Some(sequence) {
+            state.active = None;
+        }
+        state.replaying = Some(sequence);
+
+        let mut data = Vec::new();
+        match File::open(self.segment_path(sequence)) {
+            Ok(mut file) => {
+                file.read_to_end(&mut data)?;
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        Ok(Some((sequence, decode_records(&data))))
+    }
+
+    /// Completes the replay of a segment, keeping only the requests that were
+    /// not delivered.
+    pub(crate) fn complete(&self, sequence: u64, remaining: &[SpooledRequest]) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let path = self.segment_path(sequence);
+        if state.replaying == Some(sequence) {
+            state.replaying = None;
+        }
+
+        let tracked = state.segments.iter().any(|s| s.sequence == sequence);
+        if remaining.is_empty() || !tracked {
+            state.segments.retain(|s| s.sequence != sequence);
+            return remove_file_if_exists(&path);
+        }
+
+        let data: Vec<u8> = remaining.iter().flat_map(SpooledRequest::encode).collect();
+        let temp = path.with_extension("tmp");
+        {
+            let mut file = File::create(&temp)?;
+            file.write_all(&data)?;
+            file.sync_data()?;
+        }
+        fs::rename(&temp, &path)?;
+
+        if let Some(segment) = state.segments.iter_mut().find(|s| s.sequence == sequence) {
+            segment.size = data.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Ends a replay that did not [`complete`](Self::complete) its segment,
+    /// e.g. because the export was cancelled.
+    pub(crate) fn end_replay(&self) {
+        self.state.lock().unwrap().replaying = None;
+    }
+
+    fn segment_path(&self, sequence: u64) -> PathBuf {
+        self.config
+            .directory
+            .join(format!("{:020}.{}", sequence, SEGMENT_EXTENSION))
+    }
+}
+
+/// Decodes all complete records of a segment.
+///
+/// A truncated record at the end, e.g. from a crash during a write, is ignored.
+fn decode_records(mut data: &[u8]) -> Vec<SpooledRequest> {
+    let mut records = Vec::new();
+    while data.len() >= 4 {
+        let len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let Some(record) = data.get(4..4 + len) else {
+            break;
+        };
+        if let Some(request) = SpooledRequest::decode(record) {
+            records.push(request);
+        }
+        data = &data[4 + len..];
+    }
+    records
+}
+
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &str) -> SpooledRequest {
+        SpooledRequest {
+            content_type: Some("application/x-protobuf".to_string()),
+            content_encoding: None,
+            spans: 1,
+            body: Bytes::copy_from_slice(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_push_and_replay_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(SpoolConfig::new(dir.path()).with_segment_size(64)).unwrap();
+
+        for body in ["first", "second", "third", "fourth"] {
+            spool.push(&request(body)).unwrap();
+        }
+
+        let mut replayed = Vec::new();
+        while let Some((sequence, records)) = spool.oldest().unwrap() {
+            replayed.extend(records.into_iter().map(|r| r.body));
+            spool.complete(sequence, &[]).unwrap();
+        }
+
+        assert_eq!(replayed, vec!["first", "second", "third", "fourth"]);
+        assert!(spool.is_empty());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+            spool.push(&request("kept")).unwrap();
+        }
+
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        assert!(!spool.is_empty());
+        let (_, records) = spool.oldest().unwrap().unwrap();
+        assert_eq!(records, vec![request("kept")]);
+    }
+
+    #[test]
+    fn test_partial_replay_keeps_remaining_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        spool.push(&request("a")).unwrap();
+        spool.push(&request("b")).unwrap();
+
+        let (sequence, records) = spool.oldest().unwrap().unwrap();
+        spool.complete(sequence, &records[1..]).unwrap();
+
+        let (_, records) = spool.oldest().unwrap().unwrap();
+        assert_eq!(records, vec![request("b")]);
+    }
+
+    #[test]
+    fn test_sealed_segment_is_not_appended_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        spool.push(&request("old")).unwrap();
+
+        let (sequence, _) = spool.oldest().unwrap().unwrap();
+        spool.push(&request("new")).unwrap();
+        spool.complete(sequence, &[]).unwrap();
+
+        let (_, records) = spool.oldest().unwrap().unwrap();
+        assert_eq!(records, vec![request("new")]);
+    }
+
+    #[test]
+    fn test_size_cap_discards_oldest_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(
+            SpoolConfig::new(dir.path())
+                .with_segment_size(40)
+                .with_max_size(100),
+        )
+        .unwrap();
+
+        for i in 0..10 {
+            spool.push(&request(&format!("request-{}", i))).unwrap();
+        }
+
+        let mut replayed = Vec::new();
+        while let Some((sequence, records)) = spool.oldest().unwrap() {
+            replayed.extend(records.into_iter().map(|r| r.body));
+            spool.complete(sequence, &[]).unwrap();
+        }
+        assert!(replayed.len() < 10);
+        assert_eq!(replayed.last().unwrap(), "request-9");
+    }
+
+    #[test]
+    fn test_size_cap_keeps_segment_in_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(
+            SpoolConfig::new(dir.path())
+                .with_segment_size(40)
+                .with_max_size(100),
+        )
+        .unwrap();
+        spool.push(&request("request-0")).unwrap();
+
+        let (sequence, records) = spool.oldest().unwrap().unwrap();
+        for i in 1..10 {
+            spool.push(&request(&format!("request-{}", i))).unwrap();
+        }
+        spool.complete(sequence, &records).unwrap();
+
+        let (_, records) = spool.oldest().unwrap().unwrap();
+        assert_eq!(records, vec![request("request-0")]);
+        let segments = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(segments, spool.state.lock().unwrap().segments.len());
+    }
+
+    #[test]
+    fn test_truncated_record_is_ignored() {
+        let mut data = request("complete").encode();
+        let truncated = request("truncated").encode();
+        data.extend_from_slice(&truncated[..truncated.len() - 3]);
+
+        assert_eq!(decode_records(&data), vec![request("complete")]);
+    }
+}
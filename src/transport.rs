@@ -0,0 +1,812 @@
+//! HTTP transport used by the Langfuse exporter.
+//!
+//! [`LangfuseHttpClient`] wraps a `reqwest::Client` and implements the
+//! OpenTelemetry [`HttpClient`] trait, so it can be handed to the OTLP exporter.
+//! With the `blocking-client` feature it can wrap a `reqwest::blocking::Client`
+//! instead, which sends requests without an async runtime.
+//! Unlike the stock implementation it keeps the full response (status and
+//! headers) for non-success statuses, which is what allows retries to honour
+//! `Retry-After` and to tell transient from permanent failures.
+//!
+//! When a spool is configured, requests that still fail with a transient error
+//! after all retries are written to disk and replayed in order before the next
+//! request is sent.
+//!
+//! When a circuit breaker is configured, requests are not sent at all while
+//! its circuit is open.
+//!
+//! Headers set on the client override the ones of the request. With a
+//! credential provider, the Authorization header is set per request.
+
+use crate::circuit_breaker::CircuitBreaker;
+use crate::credentials::SharedCredentialProvider;
+use crate::metrics::ExporterMetrics;
+use crate::retry::{self, RetryPolicy};
+use crate::spool::{Spool, SpooledRequest};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
+use http::{request::Parts, HeaderMap, HeaderValue, Request, Response, StatusCode};
+use opentelemetry_http::{HttpClient, HttpError};
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    /// Export the requests being sent belong to.
+    static EXPORT: Export;
+}
+
+/// Export of a chunk of spans, tracked across the HTTP client.
+struct Export {
+    spans: usize,
+    status: Cell<Option<StatusCode>>,
+}
+
+/// Runs the export of `spans` spans.
+///
+/// Requests sent by `future` are spooled with the span count, and the status
+/// of the last response received is returned with the output.
+pub(crate) async fn track_export<F: Future>(
+    spans: usize,
+    future: F,
+) -> (F::Output, Option<StatusCode>) {
+    let export = Export {
+        spans,
+        status: Cell::new(None),
+    };
+    EXPORT
+        .scope(export, async {
+            let output = future.await;
+            (output, EXPORT.with(|export| export.status.get()))
+        })
+        .await
+}
+
+/// Underlying reqwest client.
+#[derive(Debug, Clone)]
+pub(crate) enum Client {
+    /// Asynchronous client, requires a Tokio runtime.
+    Async(reqwest::Client),
+    /// Blocking client, requires no async runtime.
+    #[cfg(feature = "blocking-client")]
+    Blocking(reqwest::blocking::Client),
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::Async(reqwest::Client::default())
+    }
+}
+
+impl From<reqwest::Client> for Client {
+    fn from(client: reqwest::Client) -> Self {
+        Client::Async(client)
+    }
+}
+
+#[cfg(feature = "blocking-client")]
+impl From<reqwest::blocking::Client> for Client {
+    fn from(client: reqwest::blocking::Client) -> Self {
+        Client::Blocking(client)
+    }
+}
+
+impl Client {
+    /// Sends a single `GET` request, returning the status and body of the response.
+    pub(crate) async fn get(
+        &self,
+        url: &str,
+        authorization: Option<&str>,
+        timeout: Duration,
+    ) -> Result<(StatusCode, Bytes), reqwest::Error> {
+        match self {
+            Client::Async(client) => {
+                let mut request = client.get(url).timeout(timeout);
+                if let Some(authorization) = authorization {
+                    request = request.header(AUTHORIZATION, authorization);
+                }
+                let response = request.send().await?;
+                Ok((response.status(), response.bytes().await?))
+            }
+            #[cfg(feature = "blocking-client")]
+            Client::Blocking(client) => {
+                let mut request = client.get(url).timeout(timeout);
+                if let Some(authorization) = authorization {
+                    request = request.header(AUTHORIZATION, authorization);
+                }
+                let response = request.send()?;
+                Ok((response.status(), response.bytes()?))
+            }
+        }
+    }
+
+    /// Waits for the given duration before a retry.
+    ///
+    /// The blocking client blocks the current thread, which sends its
+    /// requests anyway. The async client never blocks the executor polling it
+    /// and needs the Tokio timer, like its requests need the Tokio runtime:
+    /// returns `false` without waiting when there is none.
+    async fn sleep(&self, duration: Duration) -> bool {
+        match self {
+            Client::Async(_) => {
+                if tokio::runtime::Handle::try_current().is_err() {
+                    return false;
+                }
+                tokio::time::sleep(duration).await;
+                true
+            }
+            #[cfg(feature = "blocking-client")]
+            Client::Blocking(_) => {
+                std::thread::sleep(duration);
+                true
+            }
+        }
+    }
+}
+
+/// HTTP client that sends export requests to Langfuse.
+#[derive(Debug)]
+pub(crate) struct LangfuseHttpClient {
+    client: Client,
+    timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    spool: Option<Spool>,
+    circuit_breaker: Option<CircuitBreaker>,
+    metrics: Option<ExporterMetrics>,
+    headers: HeaderMap,
+    credential_provider: Option<SharedCredentialProvider>,
+    replaying: AtomicBool,
+}
+
+impl LangfuseHttpClient {
+    /// Creates a new client that sends each request once.
+    pub(crate) fn new(client: impl Into<Client>) -> Self {
+        Self {
+            client: client.into(),
+            timeout: None,
+            retry_policy: None,
+            spool: None,
+            circuit_breaker: None,
+            metrics: None,
+            headers: HeaderMap::new(),
+            credential_provider: None,
+            replaying: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets headers that replace the ones of the same name on every request.
+    pub(crate) fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets the provider of the Authorization header of every request.
+    pub(crate) fn with_credential_provider(
+        mut self,
+        credential_provider: Option<SharedCredentialProvider>,
+    ) -> Self {
+        self.credential_provider = credential_provider;
+        self
+    }
+
+    /// Sets the timeout applied to each individual attempt.
+    pub(crate) fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the retry policy applied to failed requests.
+    pub(crate) fn with_retry_policy(mut self, retry_policy: Option<RetryPolicy>) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the spool receiving requests that could not be delivered.
+    pub(crate) fn with_spool(mut self, spool: Option<Spool>) -> Self {
+        self.spool = spool;
+        self
+    }
+
+    /// Sets the circuit breaker guarding against requests that cannot succeed.
+    pub(crate) fn with_circuit_breaker(mut self, circuit_breaker: Option<CircuitBreaker>) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// Sets the metrics recorded for each request.
+    pub(crate) fn with_metrics(mut self, metrics: Option<ExporterMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Sends a single attempt of the request.
+    async fn send_once(
+        &self,
+        parts: &Parts,
+        body: Bytes,
+    ) -> Result<Response<Bytes>, reqwest::Error> {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_bytes_sent(body.len());
+        }
+
+        let (status, headers, body) = match &self.client {
+            Client::Async(client) => {
+                let mut request = client
+                    .request(parts.method.clone(), parts.uri.to_string())
+                    .headers(parts.headers.clone())
+                    .body(body);
+
+                if let Some(timeout) = self.timeout {
+                    request = request.timeout(timeout);
+                }
+
+                let mut response = request.send().await?;
+                let headers = std::mem::take(response.headers_mut());
+                (response.status(), headers, response.bytes().await?)
+            }
+            #[cfg(feature = "blocking-client")]
+            Client::Blocking(client) => {
+                let mut request = client
+                    .request(parts.method.clone(), parts.uri.to_string())
+                    .headers(parts.headers.clone())
+                    .body(body);
+
+                if let Some(timeout) = self.timeout {
+                    request = request.timeout(timeout);
+                }
+
+                let mut response = request.send()?;
+                let headers = std::mem::take(response.headers_mut());
+                (response.status(), headers, response.bytes()?)
+            }
+        };
+
+        let mut http_response = Response::new(body);
+        *http_response.status_mut() = status;
+        *http_response.headers_mut() = headers;
+        Ok(http_response)
+    }
+
+    /// Sends the request, retrying transient failures according to the retry policy.
+    async fn send_with_retry(
+        &self,
+        parts: &Parts,
+        body: Bytes,
+    ) -> Result<Response<Bytes>, reqwest::Error> {
+        let Some(policy) = &self.retry_policy else {
+            return self.send_once(parts, body).await;
+        };
+
+        let started = Instant::now();
+        let mut attempt = 1;
+
+        loop {
+            let result = self.send_once(parts, body.clone()).await;
+
+            let delay = match &result {
+                Ok(response) if retry::is_retryable_status(response.status()) => {
+                    retry::retry_after(response.status(), response.headers())
+                        .unwrap_or_else(|| policy.backoff(attempt))
+                }
+                Err(err) if retry::is_retryable_error(err) => policy.backoff(attempt),
+                _ => return result,
+            };
+
+            if attempt >= policy.max_attempts()
+                || started.elapsed() + delay > policy.max_elapsed_time()
+            {
+                return result;
+            }
+
+            if !self.client.sleep(delay).await {
+                return result;
+            }
+            attempt += 1;
+            if let Some(metrics) = &self.metrics {
+                metrics.record_retry();
+            }
+        }
+    }
+
+    /// Replays spooled requests in order.
+    ///
+    /// Requests rejected for good (e.g. `400`) are dropped, while `401` and
+    /// `403` stop the replay so the requests survive a key fix.
+    async fn replay_spool(&self, spool: &Spool, parts: &Parts) -> Replay {
+        // Only one replay at a time
+        if self.replaying.swap(true, Ordering::AcqRel) {
+            return Replay::Busy;
+        }
+        // Also ends the replay when the export is cancelled
+        let _guard = ReplayGuard {
+            replaying: &self.replaying,
+            spool,
+        };
+
+        loop {
+            let (sequence, requests) = match spool.oldest() {
+                Ok(Some(segment)) => segment,
+                Ok(None) => return Replay::Drained,
+                Err(_) => return Replay::Pending,
+            };
+
+            let mut delivered = 0;
+            for request in &requests {
+                let replay = replay_parts(parts, request);
+                match self.send_once(&replay, request.body.clone()).await {
+                    Ok(response) if response.status().is_success() => {
+                        if let Some(metrics) = &self.metrics {
+                            metrics.record_export(
+                                request.spans as usize,
+                                true,
+                                Some(response.status()),
+                            );
+                        }
+                    }
+                    Ok(response) if !is_transient_status(response.status()) => {}
+                    _ => break,
+                }
+                delivered += 1;
+            }
+
+            let _ = spool.complete(sequence, &requests[delivered..]);
+            if delivered < requests.len() {
+                return Replay::Pending;
+            }
+        }
+    }
+
+    /// Sends the request through the spool, if one is configured.
+    async fn send(&self, parts: &Parts, body: Bytes) -> Result<Response<Bytes>, HttpError> {
+        let Some(spool) = &self.spool else {
+            return Ok(self.send_with_retry(parts, body).await?);
+        };
+
+        // Keep this request behind the spooled ones until they are delivered
+        if !spool.is_empty() {
+            let reason = match self.replay_spool(spool, parts).await {
+                Replay::Drained => None,
+                Replay::Pending => Some("Langfuse is unreachable"),
+                Replay::Busy => Some("spooled requests are being replayed"),
+            };
+            if let Some(reason) = reason {
+                spool.push(&spooled_request(parts, body))?;
+                return Err(format!("{}, request spooled for replay", reason).into());
+            }
+        }
+
+        let result = self.send_with_retry(parts, body.clone()).await;
+        let transient = match &result {
+            Ok(response) => retry::is_retryable_status(response.status()),
+            Err(err) => retry::is_retryable_error(err),
+        };
+        if transient {
+            spool.push(&spooled_request(parts, body))?;
+        }
+
+        Ok(result?)
+    }
+}
+
+#[async_trait]
+impl HttpClient for LangfuseHttpClient {
+    async fn send_bytes(&self, request: Request<Bytes>) -> Result<Response<Bytes>, HttpError> {
+        let (mut parts, body) = request.into_parts();
+        for (name, value) in &self.headers {
+            parts.headers.insert(name, value.clone());
+        }
+        if let Some(provider) = &self.credential_provider {
+            let credentials = match provider.0.credentials().await {
+                Ok(credentials) => credentials,
+                Err(err) => {
+                    // Keep the request, the keys may be readable again later
+                    if let Some(spool) = &self.spool {
+                        spool.push(&spooled_request(&parts, body))?;
+                    }
+                    return Err(err.into());
+                }
+            };
+            let mut value = HeaderValue::try_from(credentials.auth_header().expose_secret())?;
+            value.set_sensitive(true);
+            parts.headers.insert(AUTHORIZATION, value);
+        }
+
+        let permit = match self
+            .circuit_breaker
+            .as_ref()
+            .map(CircuitBreaker::try_acquire)
+        {
+            Some(Err(reason)) => {
+                // Keep the request, it may be accepted once the configuration is fixed
+                if let Some(spool) = &self.spool {
+                    spool.push(&spooled_request(&parts, body))?;
+                }
+                return Err(format!("Langfuse export disabled: {}", reason).into());
+            }
+            Some(Ok(permit)) => Some(permit),
+            None => None,
+        };
+
+        let started = Instant::now();
+        let size = body.len();
+        let result = self.send(&parts, body).await;
+        let status = result.as_ref().ok().map(Response::status);
+
+        if let Some(permit) = permit {
+            permit.record(status);
+        }
+        let _ = EXPORT.try_with(|export| export.status.set(status));
+        if let Some(metrics) = &self.metrics {
+            metrics.record_request(started.elapsed(), size, status);
+        }
+        result
+    }
+}
+
+/// Outcome of a spool replay.
+enum Replay {
+    /// All spooled requests were delivered.
+    Drained,
+    /// Requests remain in the spool, Langfuse is still unreachable.
+    Pending,
+    /// Another export is replaying the spool.
+    Busy,
+}
+
+/// Marks the end of a spool replay when dropped.
+struct ReplayGuard<'a> {
+    replaying: &'a AtomicBool,
+    spool: &'a Spool,
+}
+
+impl Drop for ReplayGuard<'_> {
+    fn drop(&mut self) {
+        self.spool.end_replay();
+        self.replaying.store(false, Ordering::Release);
+    }
+}
+
+/// Returns whether a status may succeed later, so the request must be kept.
+fn is_transient_status(status: StatusCode) -> bool {
+    retry::is_retryable_status(status)
+        || status == StatusCode::UNAUTHORIZED
+        || status == StatusCode::FORBIDDEN
+}
+
+fn spooled_request(parts: &Parts, body: Bytes) -> SpooledRequest {
+    let header = |name| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|v: &HeaderValue| v.to_str().ok())
+            .map(str::to_string)
+    };
+
+    SpooledRequest {
+        content_type: header(CONTENT_TYPE),
+        content_encoding: header(CONTENT_ENCODING),
+        spans: EXPORT.try_with(|export| export.spans).unwrap_or(0) as u32,
+        body,
+    }
+}
+
+/// Builds the request parts for replaying a spooled request.
+///
+/// The endpoint and headers of the current request are used, so a replay
+/// always goes out with the current configuration.
+fn replay_parts(parts: &Parts, request: &SpooledRequest) -> Parts {
+    let (mut replay, ()) = Request::new(()).into_parts();
+    replay.method = parts.method.clone();
+    replay.uri = parts.uri.clone();
+    replay.headers = parts.headers.clone();
+
+    for (name, value) in [
+        (CONTENT_TYPE, &request.content_type),
+        (CONTENT_ENCODING, &request.content_encoding),
+    ] {
+        match value.as_deref().map(HeaderValue::from_str) {
+            Some(Ok(value)) => {
+                replay.headers.insert(name, value);
+            }
+            _ => {
+                replay.headers.remove(name);
+            }
+        }
+    }
+    replay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spool::SpoolConfig;
+    use crate::test_util::{MockResponse, MockServer};
+    use http::Method;
+
+    fn request(url: &str) -> Request<Bytes> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(url)
+            .header("Content-Type", "application/x-protobuf")
+            .body(Bytes::from_static(b"payload"))
+            .unwrap()
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(1))
+            .with_max_backoff(Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let server = MockServer::start(vec![
+            MockResponse::status(502),
+            MockResponse::status(503),
+            MockResponse::status(200),
+        ]);
+        let client =
+            LangfuseHttpClient::new(reqwest::Client::new()).with_retry_policy(Some(fast_policy()));
+
+        let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|r| r.body == b"payload"));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_client_errors() {
+        for status in [400, 401, 403] {
+            let server = MockServer::start(vec![MockResponse::status(status)]);
+            let client = LangfuseHttpClient::new(reqwest::Client::new())
+                .with_retry_policy(Some(fast_policy()));
+
+            let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+            assert_eq!(response.status(), status);
+            assert_eq!(server.requests().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stops_after_max_attempts() {
+        let server = MockServer::start(vec![MockResponse::status(500)]);
+        let client = LangfuseHttpClient::new(reqwest::Client::new())
+            .with_retry_policy(Some(fast_policy().with_max_attempts(3)));
+
+        let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert_eq!(response.status(), 500);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_beyond_deadline_stops_retrying() {
+        let server = MockServer::start(vec![
+            MockResponse::status(429).with_header("Retry-After", "120"),
+            MockResponse::status(200),
+        ]);
+        let client = LangfuseHttpClient::new(reqwest::Client::new()).with_retry_policy(Some(
+            fast_policy().with_max_elapsed_time(Duration::from_secs(5)),
+        ));
+
+        let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert_eq!(response.status(), 429);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honoured() {
+        let server = MockServer::start(vec![
+            MockResponse::status(503).with_header("Retry-After", "1"),
+            MockResponse::status(200),
+        ]);
+        let client =
+            LangfuseHttpClient::new(reqwest::Client::new()).with_retry_policy(Some(fast_policy()));
+
+        let started = Instant::now();
+        let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_spools_failed_requests_and_replays_them_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start(vec![
+            MockResponse::status(503),
+            MockResponse::status(503),
+            MockResponse::status(200),
+        ]);
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        let client = LangfuseHttpClient::new(reqwest::Client::new()).with_spool(Some(spool));
+
+        let first = Request::builder()
+            .method(Method::POST)
+            .uri(server.url())
+            .body(Bytes::from_static(b"first"))
+            .unwrap();
+        assert_eq!(client.send_bytes(first).await.unwrap().status(), 503);
+
+        // The replay of "first" fails, so "second" is spooled behind it
+        let second = Request::builder()
+            .method(Method::POST)
+            .uri(server.url())
+            .body(Bytes::from_static(b"second"))
+            .unwrap();
+        assert!(client.send_bytes(second).await.is_err());
+
+        // Langfuse is back: both spooled requests are replayed before "third"
+        let third = Request::builder()
+            .method(Method::POST)
+            .uri(server.url())
+            .body(Bytes::from_static(b"third"))
+            .unwrap();
+        assert_eq!(client.send_bytes(third).await.unwrap().status(), 200);
+
+        let bodies: Vec<Vec<u8>> = server.requests().into_iter().map(|r| r.body).collect();
+        assert_eq!(
+            bodies,
+            vec![
+                b"first".to_vec(),
+                b"first".to_vec(),
+                b"first".to_vec(),
+                b"second".to_vec(),
+                b"third".to_vec(),
+            ]
+        );
+        assert!(client.spool.as_ref().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replays_spool_left_by_previous_process() {
+        let dir = tempfile::tempdir().unwrap();
+        Spool::open(SpoolConfig::new(dir.path()))
+            .unwrap()
+            .push(&SpooledRequest {
+                content_type: Some("application/json".to_string()),
+                content_encoding: None,
+                spans: 1,
+                body: Bytes::from_static(b"from-last-run"),
+            })
+            .unwrap();
+
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        let client = LangfuseHttpClient::new(reqwest::Client::new()).with_spool(Some(spool));
+
+        client.send_bytes(request(&server.url())).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body, b"from-last-run");
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        assert_eq!(requests[1].body, b"payload");
+    }
+
+    #[tokio::test]
+    async fn test_exports_during_replay_are_spooled_behind_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start(vec![MockResponse::status(200)]);
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        spool
+            .push(&spooled_request(
+                &request(&server.url()).into_parts().0,
+                "old".into(),
+            ))
+            .unwrap();
+        let client = LangfuseHttpClient::new(reqwest::Client::new()).with_spool(Some(spool));
+
+        client.replaying.store(true, Ordering::Release);
+        assert!(client.send_bytes(request(&server.url())).await.is_err());
+        assert!(server.requests().is_empty());
+
+        let spool = client.spool.as_ref().unwrap();
+        let (_, records) = spool.oldest().unwrap().unwrap();
+        let bodies: Vec<_> = records.into_iter().map(|r| r.body).collect();
+        assert_eq!(bodies, vec!["old", "payload"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_replay_can_start_again() {
+        let dir = tempfile::tempdir().unwrap();
+        // Accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        spool
+            .push(&spooled_request(
+                &request(&url).into_parts().0,
+                "old".into(),
+            ))
+            .unwrap();
+        let client = LangfuseHttpClient::new(reqwest::Client::new()).with_spool(Some(spool));
+
+        let export = client.send_bytes(request(&url));
+        assert!(tokio::time::timeout(Duration::from_millis(50), export)
+            .await
+            .is_err());
+
+        assert!(!client.replaying.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_spool_permanent_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start(vec![MockResponse::status(400)]);
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        let client = LangfuseHttpClient::new(reqwest::Client::new()).with_spool(Some(spool));
+
+        client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert!(client.spool.as_ref().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_stops_requests_until_probe_succeeds() {
+        let server = MockServer::start(vec![
+            MockResponse::status(401),
+            MockResponse::status(401),
+            MockResponse::status(200),
+        ]);
+        let breaker = CircuitBreaker::new()
+            .with_failure_threshold(2)
+            .with_probe_interval(Duration::from_millis(50));
+        let client = LangfuseHttpClient::new(reqwest::Client::new())
+            .with_circuit_breaker(Some(breaker.clone()));
+
+        for _ in 0..2 {
+            let response = client.send_bytes(request(&server.url())).await.unwrap();
+            assert_eq!(response.status(), 401);
+        }
+        let err = client.send_bytes(request(&server.url())).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Langfuse export disabled: invalid credentials"
+        );
+        assert_eq!(server.requests().len(), 2);
+        assert!(breaker.is_open());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(server.requests().len(), 3);
+        assert!(!breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_spools_rejected_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = MockServer::start(vec![MockResponse::status(404)]);
+        let spool = Spool::open(SpoolConfig::new(dir.path())).unwrap();
+        let client = LangfuseHttpClient::new(reqwest::Client::new())
+            .with_spool(Some(spool))
+            .with_circuit_breaker(Some(CircuitBreaker::new().with_failure_threshold(1)));
+
+        client.send_bytes(request(&server.url())).await.unwrap();
+        assert!(client.spool.as_ref().unwrap().is_empty());
+
+        assert!(client.send_bytes(request(&server.url())).await.is_err());
+        assert!(!client.spool.as_ref().unwrap().is_empty());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_without_policy_sends_once() {
+        let server = MockServer::start(vec![MockResponse::status(503)]);
+        let client = LangfuseHttpClient::new(reqwest::Client::new());
+
+        let response = client.send_bytes(request(&server.url())).await.unwrap();
+
+        assert_eq!(response.status(), 503);
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,373 @@
+//! Connectivity and credential check of an exporter configuration.
+//!
+//! [`ExporterBuilder::verify`](crate::ExporterBuilder::verify) calls the health
+//! and project endpoints of the configured Langfuse host before any span is
+//! exported, and reports why the configuration cannot work as a
+//! [`VerifyError`]:
+//!
+//! - the host cannot be reached, or the TLS handshake fails
+//! - the public and secret keys are swapped
+//! - the keys are rejected by the configured cloud region but accepted by
+//!   another one of the allowed regions
+//! - the keys are rejected
+
+use crate::auth;
+use crate::constants::{DEFAULT_LANGFUSE_HOST, HIPAA_LANGFUSE_HOST, US_LANGFUSE_HOST};
+use crate::region::LangfuseRegion;
+use crate::transport::Client;
+use http::StatusCode;
+use serde::Deserialize;
+use std::error::Error as _;
+use std::time::Duration;
+
+/// Langfuse cloud hosts, one per region.
+pub(crate) const CLOUD_HOSTS: &[&str] =
+    &[DEFAULT_LANGFUSE_HOST, US_LANGFUSE_HOST, HIPAA_LANGFUSE_HOST];
+
+/// Returns the cloud hosts of the regions named in `allowed`.
+///
+/// Only these are tried with keys rejected by the configured region, so the
+/// keys never reach a region that was not allowed explicitly.
+pub(crate) fn cloud_hosts(allowed: &[LangfuseRegion]) -> Vec<&'static str> {
+    CLOUD_HOSTS
+        .iter()
+        .copied()
+        .filter(|host| allowed.iter().any(|region| region.contains(host)))
+        .collect()
+}
+
+/// Timeout of verification requests, unless the builder sets one.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of a successful verification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verification {
+    /// Base URL of the verified Langfuse host.
+    pub host: String,
+    /// Version reported by the Langfuse server, if any.
+    pub version: Option<String>,
+    /// ID of the project the keys belong to, if reported.
+    pub project_id: Option<String>,
+    /// Name of the project the keys belong to, if reported.
+    pub project_name: Option<String>,
+}
+
+/// Reason why an exporter configuration cannot export to Langfuse.
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    /// The Langfuse host could not be reached.
+    #[error("Langfuse host {host} is unreachable: {source}")]
+    HostUnreachable {
+        /// Base URL of the host.
+        host: String,
+        /// The underlying connection error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The TLS handshake with the Langfuse host failed, e.g. because its
+    /// certificate is not trusted.
+    #[error("TLS handshake with Langfuse host {host} failed: {source}")]
+    Tls {
+        /// Base URL of the host.
+        host: String,
+        /// The underlying TLS error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The public and secret keys are swapped.
+    #[error("The Langfuse public and secret keys are swapped")]
+    KeysSwapped,
+
+    /// The keys belong to another Langfuse cloud region.
+    #[error("The Langfuse keys belong to {region_host}, not to {host}")]
+    WrongRegion {
+        /// Base URL of the configured host.
+        host: String,
+        /// Base URL of the region that accepted the keys.
+        region_host: String,
+    },
+
+    /// Langfuse rejected the key pair.
+    #[error("Langfuse host {host} rejected the key pair with status {status}")]
+    InvalidCredentials {
+        /// Base URL of the host.
+        host: String,
+        /// Status of the rejected request.
+        status: u16,
+    },
+
+    /// Langfuse answered with an unexpected status, e.g. because the host is
+    /// not a Langfuse instance.
+    #[error("Unexpected status {status} from {url}")]
+    UnexpectedResponse {
+        /// URL of the request.
+        url: String,
+        /// Status of the response.
+        status: u16,
+    },
+
+    /// The exporter configuration is incomplete or invalid.
+    #[error(transparent)]
+    Configuration(#[from] crate::Error),
+}
+
+/// Response of `/api/public/health`.
+#[derive(Debug, Deserialize)]
+struct HealthResponse {
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// Response of `/api/public/projects`.
+#[derive(Debug, Deserialize)]
+struct ProjectsResponse {
+    #[serde(default)]
+    data: Vec<Project>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// Verifies that `authorization` is accepted by the Langfuse instance at `host`.
+///
+/// When the keys are rejected by one of the `cloud_hosts`, the other ones are
+/// tried to detect keys of another region.
+pub(crate) async fn verify(
+    client: &Client,
+    host: &str,
+    authorization: &str,
+    timeout: Duration,
+    cloud_hosts: &[&str],
+) -> Result<Verification, VerifyError> {
+    if keys_swapped(authorization) {
+        return Err(VerifyError::KeysSwapped);
+    }
+
+    let url = format!("{}/api/public/health", host);
+    let (status, body) = client
+        .get(&url, None, timeout)
+        .await
+        .map_err(|source| connection_error(host, source))?;
+    if !status.is_success() {
+        return Err(VerifyError::UnexpectedResponse {
+            url,
+            status: status.as_u16(),
+        });
+    }
+    let version = serde_json::from_slice::<HealthResponse>(&body)
+        .ok()
+        .and_then(|health| health.version);
+
+    let (status, body) = get_projects(client, host, authorization, timeout)
+        .await
+        .map_err(|source| connection_error(host, source))?;
+    match status {
+        status if status.is_success() => {
+            let project = serde_json::from_slice::<ProjectsResponse>(&body)
+                .ok()
+                .and_then(|projects| projects.data.into_iter().next());
+            Ok(Verification {
+                host: host.to_string(),
+                version,
+                project_id: project.as_ref().and_then(|project| project.id.clone()),
+                project_name: project.and_then(|project| project.name),
+            })
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            if cloud_hosts.contains(&host) {
+                for region_host in cloud_hosts.iter().filter(|region| **region != host) {
+                    let accepted = get_projects(client, region_host, authorization, timeout)
+                        .await
+                        .is_ok_and(|(status, _)| status.is_success());
+                    if accepted {
+                        return Err(VerifyError::WrongRegion {
+                            host: host.to_string(),
+                            region_host: region_host.to_string(),
+                        });
+                    }
+                }
+            }
+            Err(VerifyError::InvalidCredentials {
+                host: host.to_string(),
+                status: status.as_u16(),
+            })
+        }
+        status => Err(VerifyError::UnexpectedResponse {
+            url: format!("{}/api/public/projects", host),
+            status: status.as_u16(),
+        }),
+    }
+}
+
+/// Requests the projects the keys belong to.
+async fn get_projects(
+    client: &Client,
+    host: &str,
+    authorization: &str,
+    timeout: Duration,
+) -> Result<(StatusCode, bytes::Bytes), reqwest::Error> {
+    let url = format!("{}/api/public/projects", host);
+    client.get(&url, Some(authorization), timeout).await
+}
+
+/// Returns whether a Basic authorization header holds the secret key in
+/// place of the public key and vice versa.
+fn keys_swapped(authorization: &str) -> bool {
+    let Some((public_key, secret_key)) = auth::decode_basic_auth(authorization) else {
+        return false;
+    };
+    public_key.starts_with("sk-lf-") && secret_key.expose_secret().starts_with("pk-lf-")
+}
+
+/// Classifies a failed request as a TLS or a connection failure.
+fn connection_error(host: &str, source: reqwest::Error) -> VerifyError {
+    let mut cause: Option<&dyn std::error::Error> = source.source();
+    while let Some(err) = cause {
+        let message = err.to_string().to_ascii_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|word| message.contains(word))
+        {
+            return VerifyError::Tls {
+                host: host.to_string(),
+                source,
+            };
+        }
+        cause = err.source();
+    }
+    VerifyError::HostUnreachable {
+        host: host.to_string(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::build_auth_header;
+    use crate::test_util::{MockResponse, MockServer};
+
+    async fn verify_with(
+        server: &MockServer,
+        cloud_hosts: &[&str],
+    ) -> Result<Verification, VerifyError> {
+        verify(
+            &Client::default(),
+            &server.url(),
+            &build_auth_header("pk-lf-test", "sk-lf-test"),
+            DEFAULT_TIMEOUT,
+            cloud_hosts,
+        )
+        .await
+    }
+
+    #[test]
+    fn test_keys_swapped() {
+        assert!(keys_swapped(&build_auth_header("sk-lf-1", "pk-lf-1")));
+        assert!(!keys_swapped(&build_auth_header("pk-lf-1", "sk-lf-1")));
+        assert!(!keys_swapped("Bearer token"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_project() {
+        let server = MockServer::start(vec![
+            MockResponse::status(200).with_body(r#"{"status":"OK","version":"3.1.0"}"#),
+            MockResponse::status(200).with_body(r#"{"data":[{"id":"p1","name":"Demo"}]}"#),
+        ]);
+
+        let verification = verify_with(&server, &[]).await.unwrap();
+
+        assert_eq!(
+            verification,
+            Verification {
+                host: server.url(),
+                version: Some("3.1.0".into()),
+                project_id: Some("p1".into()),
+                project_name: Some("Demo".into()),
+            }
+        );
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/public/health");
+        assert_eq!(requests[1].path, "/api/public/projects");
+        assert_eq!(
+            requests[1].header("authorization"),
+            Some(build_auth_header("pk-lf-test", "sk-lf-test").as_str())
+        );
+    }
+
+    #[test]
+    fn test_only_allowed_regions_are_probed() {
+        assert!(cloud_hosts(&[]).is_empty());
+        assert_eq!(
+            cloud_hosts(&[
+                LangfuseRegion::Eu,
+                LangfuseRegion::Us,
+                LangfuseRegion::SelfHosted("https://langfuse.example.com".into()),
+            ]),
+            [DEFAULT_LANGFUSE_HOST, US_LANGFUSE_HOST]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_invalid_credentials() {
+        let server = MockServer::start(vec![MockResponse::status(200), MockResponse::status(401)]);
+
+        let result = verify_with(&server, &[]).await;
+
+        assert!(matches!(
+            result,
+            Err(VerifyError::InvalidCredentials { status: 401, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_wrong_region() {
+        let configured =
+            MockServer::start(vec![MockResponse::status(200), MockResponse::status(401)]);
+        let other = MockServer::start(vec![MockResponse::status(200)]);
+        let (configured_url, other_url) = (configured.url(), other.url());
+
+        let result = verify_with(&configured, &[&configured_url, &other_url]).await;
+
+        match result {
+            Err(VerifyError::WrongRegion { host, region_host }) => {
+                assert_eq!(host, configured_url);
+                assert_eq!(region_host, other_url);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_unreachable_host() {
+        let result = verify(
+            &Client::default(),
+            "http://127.0.0.1:9",
+            &build_auth_header("pk-lf-test", "sk-lf-test"),
+            DEFAULT_TIMEOUT,
+            &[],
+        )
+        .await;
+
+        assert!(matches!(result, Err(VerifyError::HostUnreachable { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_non_langfuse_host() {
+        let server = MockServer::start(vec![MockResponse::status(404)]);
+
+        let result = verify_with(&server, &[]).await;
+
+        assert!(matches!(
+            result,
+            Err(VerifyError::UnexpectedResponse { status: 404, .. })
+        ));
+    }
+}